@@ -57,6 +57,22 @@ impl UnixInstant {
             .checked_sub(duration.secs)
             .map(|secs| Self { secs })
     }
+
+    /// Return a human readable `DateTime` for this instant. This is equivalent
+    /// to `DateTime::from(instant)`.
+    ///
+    /// ```
+    /// use clocksource::coarse::{Duration, UnixInstant};
+    ///
+    /// let instant = UnixInstant::EPOCH + Duration::from_secs(90);
+    /// assert_eq!(
+    ///     instant.to_datetime().to_string(),
+    ///     "1970-01-01T00:01:30.000+00:00"
+    /// );
+    /// ```
+    pub fn to_datetime(&self) -> crate::datetime::DateTime {
+        crate::datetime::DateTime::from(*self)
+    }
 }
 
 impl Add<Duration> for UnixInstant {
@@ -54,6 +54,19 @@ impl AtomicInstant {
         self.ns.store(value.ns, ordering)
     }
 
+    /// Sets the instant to the provided value. This is a `store` with
+    /// `Release` ordering, which pairs with loads using `Acquire` ordering.
+    ///
+    /// This is useful in tests to move a time-based type to a known instant.
+    pub fn set(&self, value: Instant) {
+        self.store(value, Ordering::Release)
+    }
+
+    /// Sets the instant to the current instant. See [`AtomicInstant::set`].
+    pub fn set_now(&self) {
+        self.set(Instant::now())
+    }
+
     /// Replaces the value of the instant and returns the previous value.
     ///
     /// See: [`core::sync::atomic::AtomicU64::swap`] for a description of the
@@ -193,3 +206,21 @@ impl From<crate::coarse::Instant> for AtomicInstant {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set() {
+        let instant = AtomicInstant::default();
+
+        let value = Instant::now() + Duration::from_secs(1);
+        instant.set(value);
+        assert_eq!(instant.load(Ordering::Acquire), value);
+
+        let before = Instant::now();
+        instant.set_now();
+        assert!(instant.load(Ordering::Acquire) >= before);
+    }
+}
@@ -56,6 +56,22 @@ impl UnixInstant {
     pub fn checked_sub(&self, duration: Duration) -> Option<Self> {
         self.ns.checked_sub(duration.ns).map(|ns| Self { ns })
     }
+
+    /// Return a human readable `DateTime` for this instant. This is equivalent
+    /// to `DateTime::from(instant)`.
+    ///
+    /// ```
+    /// use clocksource::precise::{Duration, UnixInstant};
+    ///
+    /// let instant = UnixInstant::EPOCH + Duration::from_millis(1500);
+    /// assert_eq!(
+    ///     instant.to_datetime().to_string(),
+    ///     "1970-01-01T00:00:01.500+00:00"
+    /// );
+    /// ```
+    pub fn to_datetime(&self) -> crate::datetime::DateTime {
+        crate::datetime::DateTime::from(*self)
+    }
 }
 
 impl Add<Duration> for UnixInstant {
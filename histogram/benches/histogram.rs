@@ -18,6 +18,16 @@ macro_rules! benchmark {
 fn histogram(c: &mut Criterion) {
     let mut histogram = histogram::Histogram::new(7, 64).unwrap();
     benchmark!("histogram", histogram, c);
+
+    let mut group = c.benchmark_group("histogram");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("increment_unchecked/1", |b| {
+        b.iter(|| histogram.increment_unchecked(1))
+    });
+    group.bench_function("increment_unchecked/max", |b| {
+        b.iter(|| histogram.increment_unchecked(u64::MAX))
+    });
+    group.finish();
 }
 
 fn atomic(c: &mut Criterion) {
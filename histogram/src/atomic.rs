@@ -43,12 +43,34 @@ impl AtomicHistogram {
         Ok(())
     }
 
+    /// Increment the bucket that contains the value by some count, returning
+    /// an error instead of wrapping if the bucket would overflow.
+    ///
+    /// Unlike [`add`](AtomicHistogram::add), this uses a compare-and-swap loop
+    /// and is therefore slower under contention. The bucket is not modified if
+    /// an error is returned.
+    pub fn add_checked(&self, value: u64, count: u64) -> Result<(), Error> {
+        let index = self.config.value_to_index(value)?;
+        self.buckets[index]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |v| {
+                v.checked_add(count)
+            })
+            .map(|_| ())
+            .map_err(|_| Error::Overflow)
+    }
+
     // NOTE: once stabilized, `target_has_atomic_load_store` is more correct. https://github.com/rust-lang/rust/issues/94039
     #[cfg(target_has_atomic = "64")]
     /// Drains the bucket values into a new Histogram
     ///
     /// Unlike [`load`](AtomicHistogram::load), this method will reset all bucket values to zero. This uses [`AtomicU64::swap`] and is not available
     /// on platforms where [`AtomicU64::swap`] is not available.
+    ///
+    /// Each bucket is read and reset in a single atomic operation, so an
+    /// increment which races with the drain is either included in the returned
+    /// histogram or remains in this histogram for the next drain. This makes it
+    /// suitable for per-interval reporting, unlike a `load` followed by a
+    /// reset, which can lose increments made in between.
     pub fn drain(&self) -> Histogram {
         let buckets: Vec<u64> = self
             .buckets
@@ -62,6 +84,22 @@ impl AtomicHistogram {
         }
     }
 
+    /// Returns the number of bytes of heap memory used to store the buckets.
+    pub fn heap_size(&self) -> usize {
+        self.buckets.len() * core::mem::size_of::<AtomicU64>()
+    }
+
+    /// Returns the number of buckets which currently have a non-zero count.
+    ///
+    /// Buckets are read individually, so concurrent updates may or may not be
+    /// reflected in the result.
+    pub fn nonzero_bucket_count(&self) -> usize {
+        self.buckets
+            .iter()
+            .filter(|bucket| bucket.load(Ordering::Relaxed) > 0)
+            .count()
+    }
+
     /// Read the bucket values into a new `Histogram`
     pub fn load(&self) -> Histogram {
         let buckets: Vec<u64> = self
@@ -87,6 +125,23 @@ mod test {
         assert_eq!(std::mem::size_of::<AtomicHistogram>(), 48);
     }
 
+    #[test]
+    fn heap_size() {
+        let histogram = AtomicHistogram::new(7, 64).unwrap();
+        assert_eq!(histogram.heap_size(), 7424 * 8);
+    }
+
+    #[test]
+    fn nonzero_bucket_count() {
+        let histogram = AtomicHistogram::new(7, 64).unwrap();
+        assert_eq!(histogram.nonzero_bucket_count(), 0);
+
+        histogram.increment(1).unwrap();
+        histogram.increment(1).unwrap();
+        histogram.increment(1000).unwrap();
+        assert_eq!(histogram.nonzero_bucket_count(), 2);
+    }
+
     #[cfg(target_has_atomic = "64")]
     #[test]
     /// Tests that drain properly resets buckets to 0
@@ -115,6 +170,55 @@ mod test {
         );
     }
 
+    #[cfg(target_has_atomic = "64")]
+    #[test]
+    /// Tests that draining concurrently with increments loses no counts
+    fn drain_concurrent() {
+        use std::sync::Arc;
+
+        const THREADS: u64 = 4;
+        const INCREMENTS: u64 = 100_000;
+
+        let histogram = Arc::new(AtomicHistogram::new(7, 64).unwrap());
+
+        let writers: Vec<_> = (0..THREADS)
+            .map(|thread| {
+                let histogram = histogram.clone();
+                std::thread::spawn(move || {
+                    for i in 0..INCREMENTS {
+                        histogram.increment(thread * INCREMENTS + i).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        let mut total = Histogram::new(7, 64).unwrap();
+        while writers.iter().any(|writer| !writer.is_finished()) {
+            total = total.checked_add(&histogram.drain()).unwrap();
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        total = total.checked_add(&histogram.drain()).unwrap();
+
+        assert_eq!(total.as_slice().iter().sum::<u64>(), THREADS * INCREMENTS);
+        assert_eq!(histogram.nonzero_bucket_count(), 0);
+    }
+
+    #[test]
+    // Tests that checked add rejects overflow without modifying the bucket
+    fn add_checked() {
+        let histogram = AtomicHistogram::new(7, 64).unwrap();
+
+        assert_eq!(histogram.add_checked(1, u64::MAX - 1), Ok(()));
+        assert_eq!(histogram.add_checked(1, 1), Ok(()));
+        assert_eq!(histogram.add_checked(1, 1), Err(Error::Overflow));
+        assert_eq!(histogram.load().as_slice()[1], u64::MAX);
+
+        assert_eq!(histogram.add_checked(2, 0), Ok(()));
+        assert_eq!(histogram.load().as_slice()[2], 0);
+    }
+
     #[test]
     // Tests percentiles
     fn percentiles() {
@@ -0,0 +1,117 @@
+use crate::{Config, Error, Histogram};
+
+/// A [`crate::Histogram`] which aggregates histograms from multiple sources,
+/// such as shards or hosts, while tracking how many sources contributed.
+///
+/// The aggregate histogram is the sum of the histograms from each source. The
+/// number of sources can be used to compute per-source averages, such as the
+/// average number of requests handled per shard.
+///
+/// ```
+/// use histogram::{AveragedHistogram, Histogram};
+///
+/// let mut shard = Histogram::new(7, 64).unwrap();
+/// shard.add(100, 10).unwrap();
+///
+/// let mut averaged = AveragedHistogram::new(7, 64).unwrap();
+/// averaged.add_source(&shard).unwrap();
+/// averaged.add_source(&shard).unwrap();
+///
+/// assert_eq!(averaged.sources(), 2);
+/// assert_eq!(averaged.mean_per_source(), Some(10.0));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AveragedHistogram {
+    histogram: Histogram,
+    sources: u64,
+}
+
+impl AveragedHistogram {
+    /// Construct a new histogram with no sources from the provided parameters.
+    /// See the documentation for [`crate::Config`] to understand their meaning.
+    pub fn new(grouping_power: u8, max_value_power: u8) -> Result<Self, Error> {
+        let config = Config::new(grouping_power, max_value_power)?;
+
+        Ok(Self::with_config(&config))
+    }
+
+    /// Creates a new histogram with no sources using a provided
+    /// [`crate::Config`].
+    pub fn with_config(config: &Config) -> Self {
+        Self {
+            histogram: Histogram::with_config(config),
+            sources: 0,
+        }
+    }
+
+    /// Adds the histogram from a single source to the aggregate and increments
+    /// the number of sources.
+    ///
+    /// An error is returned if the histograms have incompatible parameters or
+    /// if there is an overflow. Neither the aggregate nor the number of sources
+    /// are modified if an error is returned.
+    pub fn add_source(&mut self, source: &Histogram) -> Result<(), Error> {
+        self.histogram.add_assign_checked(source)?;
+        self.sources += 1;
+
+        Ok(())
+    }
+
+    /// Returns the aggregate histogram.
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Returns the number of sources which have been added.
+    pub fn sources(&self) -> u64 {
+        self.sources
+    }
+
+    /// Returns the mean total count per source, which is the total count of
+    /// the aggregate histogram divided by the number of sources. Returns `None`
+    /// if no sources have been added.
+    pub fn mean_per_source(&self) -> Option<f64> {
+        if self.sources == 0 {
+            return None;
+        }
+
+        let total_count: u128 = self.histogram.as_slice().iter().map(|v| *v as u128).sum();
+
+        Some(total_count as f64 / self.sources as f64)
+    }
+
+    /// Returns the aggregate histogram.
+    pub fn into_inner(self) -> Histogram {
+        self.histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_source() {
+        let mut averaged = AveragedHistogram::new(7, 64).unwrap();
+        assert_eq!(averaged.mean_per_source(), None);
+
+        let mut a = Histogram::new(7, 64).unwrap();
+        let _ = a.add(1, 10);
+        let mut b = Histogram::new(7, 64).unwrap();
+        let _ = b.add(1000, 20);
+        let c = Histogram::new(7, 64).unwrap();
+
+        averaged.add_source(&a).unwrap();
+        averaged.add_source(&b).unwrap();
+        averaged.add_source(&c).unwrap();
+
+        assert_eq!(averaged.sources(), 3);
+        assert_eq!(averaged.mean_per_source(), Some(10.0));
+        assert_eq!(averaged.histogram(), &a.checked_add(&b).unwrap());
+
+        // incompatible sources are not counted
+        let d = Histogram::new(6, 64).unwrap();
+        assert_eq!(averaged.add_source(&d), Err(Error::IncompatibleParameters));
+        assert_eq!(averaged.sources(), 3);
+    }
+}
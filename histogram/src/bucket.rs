@@ -9,6 +9,21 @@ pub struct Bucket {
 }
 
 impl Bucket {
+    /// Creates a bucket with the provided count and range. This is primarily
+    /// useful for constructing expected values to compare against.
+    ///
+    /// ```
+    /// use histogram::{Bucket, Histogram};
+    ///
+    /// let mut histogram = Histogram::new(7, 64).unwrap();
+    /// histogram.increment(1).unwrap();
+    ///
+    /// assert_eq!(histogram.percentile(50.0), Ok(Some(Bucket::new(1, 1..=1))));
+    /// ```
+    pub fn new(count: u64, range: RangeInclusive<u64>) -> Self {
+        Self { count, range }
+    }
+
     /// Returns the number of observations within the bucket's range.
     pub fn count(&self) -> u64 {
         self.count
@@ -28,4 +43,52 @@ impl Bucket {
     pub fn end(&self) -> u64 {
         *self.range.end()
     }
+
+    /// Returns the maximum relative error for values within the bucket. This
+    /// is the width of the bucket relative to its lower bound.
+    ///
+    /// Buckets which hold a single value, such as those in the linear range of
+    /// a histogram, have no error. Buckets in the logarithmic range have an
+    /// error of at most `2^-grouping_power`.
+    pub fn relative_error(&self) -> f64 {
+        let width = self.end() - self.start();
+
+        if width == 0 {
+            0.0
+        } else {
+            (width as f64 + 1.0) / self.start() as f64
+        }
+    }
+}
+
+/// Returns the midpoint of a bucket's range, rounded down. This is used as the
+/// representative value for all the observations within a bucket.
+pub(crate) fn midpoint(range: &RangeInclusive<u64>) -> u64 {
+    range.start() + (range.end() - range.start()) / 2
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Histogram;
+
+    #[test]
+    fn relative_error() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        let _ = histogram.increment(0);
+        let _ = histogram.increment(100);
+        let _ = histogram.increment(1000);
+
+        let buckets: Vec<_> = histogram.into_iter().filter(|b| b.count() > 0).collect();
+
+        // buckets in the linear range hold a single value
+        assert_eq!(buckets[0].range(), 0..=0);
+        assert_eq!(buckets[0].relative_error(), 0.0);
+        assert_eq!(buckets[1].range(), 100..=100);
+        assert_eq!(buckets[1].relative_error(), 0.0);
+
+        // buckets in the log range are bounded by the grouping power
+        assert_eq!(buckets[2].range(), 1000..=1003);
+        assert_eq!(buckets[2].relative_error(), 4.0 / 1000.0);
+        assert!(buckets[2].relative_error() <= 2.0_f64.powi(-7));
+    }
 }
@@ -0,0 +1,59 @@
+use crate::{Error, Histogram};
+
+/// An extension trait which allows an iterator of values to be collected into
+/// a [`crate::Histogram`].
+///
+/// ```
+/// use histogram::HistogramCollect;
+///
+/// let histogram = (0..1000u64).collect_histogram(7, 32).unwrap();
+///
+/// assert_eq!(histogram.as_slice().iter().sum::<u64>(), 1000);
+/// ```
+pub trait HistogramCollect {
+    /// Consumes the iterator and returns a histogram with the provided
+    /// parameters containing every value.
+    ///
+    /// An error is returned if the parameters are invalid or if any value is
+    /// outside of the storable range.
+    fn collect_histogram(self, grouping_power: u8, max_value_power: u8)
+        -> Result<Histogram, Error>;
+}
+
+impl<I: Iterator<Item = u64>> HistogramCollect for I {
+    fn collect_histogram(
+        self,
+        grouping_power: u8,
+        max_value_power: u8,
+    ) -> Result<Histogram, Error> {
+        let mut histogram = Histogram::new(grouping_power, max_value_power)?;
+
+        for value in self {
+            histogram.increment(value)?;
+        }
+
+        Ok(histogram)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_histogram() {
+        let histogram = [1, 1, 2, 1000]
+            .into_iter()
+            .collect_histogram(7, 32)
+            .unwrap();
+        assert_eq!(histogram.as_slice()[1], 2);
+        assert_eq!(histogram.as_slice()[2], 1);
+        assert_eq!(histogram.as_slice().iter().sum::<u64>(), 4);
+
+        assert_eq!(
+            [1, u64::MAX].into_iter().collect_histogram(7, 32),
+            Err(Error::OutOfRange)
+        );
+        assert_eq!((0..10).collect_histogram(7, 7), Err(Error::MaxPowerTooLow));
+    }
+}
@@ -135,6 +135,29 @@ impl Config {
         self.max_value_power
     }
 
+    /// Checks whether histograms with this config can be combined with
+    /// histograms using the other config, such as with `checked_add`.
+    ///
+    /// Returns an error describing which parameter differs if the configs are
+    /// not identical. The grouping power is checked first.
+    pub fn compatible_with(&self, other: &Config) -> Result<(), Error> {
+        if self.grouping_power != other.grouping_power {
+            return Err(Error::IncompatibleGroupingPower {
+                this: self.grouping_power,
+                other: other.grouping_power,
+            });
+        }
+
+        if self.max_value_power != other.max_value_power {
+            return Err(Error::IncompatibleMaxValuePower {
+                this: self.max_value_power,
+                other: other.max_value_power,
+            });
+        }
+
+        Ok(())
+    }
+
     /// Returns the relative error (in percentage) of this configuration. This
     /// only applies to the logarithmic bins of the histogram (linear bins have
     /// a width of 1 and no error). For histograms with no logarithmic bins,
@@ -162,11 +185,25 @@ impl Config {
             return Err(Error::OutOfRange);
         }
 
+        Ok(self.value_to_index_unchecked(value))
+    }
+
+    /// Converts a value to a bucket index without checking that the value is
+    /// within the range for the config. The caller must ensure that the value
+    /// is less than or equal to the max value for the config.
+    #[inline]
+    pub(crate) fn value_to_index_unchecked(&self, value: u64) -> usize {
+        debug_assert!(value <= self.max, "value is outside of the storable range");
+
+        if value < self.cutoff_value {
+            return value as usize;
+        }
+
         let power = 63 - value.leading_zeros();
         let log_bin = power - self.cutoff_power as u32;
         let offset = (value - (1 << power)) >> (power - self.grouping_power as u32);
 
-        Ok((self.lower_bin_count + log_bin * self.upper_bin_divisions + offset as u32) as usize)
+        (self.lower_bin_count + log_bin * self.upper_bin_divisions + offset as u32) as usize
     }
 
     /// Convert a bucket index to a lower bound.
@@ -200,6 +237,77 @@ impl Config {
     pub(crate) fn index_to_range(&self, index: usize) -> RangeInclusive<u64> {
         self.index_to_lower_bound(index)..=self.index_to_upper_bound(index)
     }
+
+    /// Returns the inclusive range of values for every bucket, in index order.
+    ///
+    /// This allocates one range per bucket, which may be large for configs
+    /// with a high grouping power. See [`Config::bucket_bounds_iter`] for a
+    /// non-allocating alternative.
+    pub fn bucket_bounds(&self) -> Vec<RangeInclusive<u64>> {
+        self.bucket_bounds_iter().collect()
+    }
+
+    /// Returns an iterator over the inclusive range of values for every bucket,
+    /// in index order.
+    pub fn bucket_bounds_iter(&self) -> impl Iterator<Item = RangeInclusive<u64>> {
+        let config = *self;
+
+        (0..self.total_buckets()).map(move |index| config.index_to_range(index))
+    }
+}
+
+#[cfg(feature = "testing")]
+impl Config {
+    /// Returns a random value which is uniformly distributed over the range of
+    /// values which can be stored in a histogram with this config.
+    ///
+    /// This is intended for generating data for tests and benchmarks only.
+    pub fn random_value(&self, rng: &mut impl rand::Rng) -> u64 {
+        let max = if self.max_value_power == 64 {
+            u64::MAX
+        } else {
+            (1 << self.max_value_power) - 1
+        };
+
+        rng.gen_range(0..=max)
+    }
+
+    /// Returns `n` random values which are uniformly distributed over the range
+    /// of values which can be stored in a histogram with this config. See
+    /// [`Config::random_value`].
+    ///
+    /// This is intended for generating data for tests and benchmarks only.
+    pub fn random_values(&self, rng: &mut impl rand::Rng, n: usize) -> Vec<u64> {
+        (0..n).map(|_| self.random_value(rng)).collect()
+    }
+}
+
+/// Returns the trade-off between relative error and memory for each valid
+/// grouping power with the provided max value power. Each entry is the tuple
+/// `(grouping_power, error, total_buckets)`, where `error` is the relative
+/// error in percent as reported by [`Config::error`].
+///
+/// Entries are sorted by grouping power, so the error decreases and the number
+/// of buckets increases with each entry. This can be used to present the
+/// options to someone choosing a configuration. Grouping powers which would
+/// need more than `u32::MAX` buckets are omitted. If the max value power is
+/// not valid, the result is empty.
+pub fn config_tradeoffs(max_value_power: u8) -> Vec<(u8, f64, usize)> {
+    (0..max_value_power.min(64))
+        .take_while(|grouping_power| {
+            let lower = 1_u128 << (grouping_power + 1);
+            let upper = (max_value_power - grouping_power - 1) as u128 * (1_u128 << grouping_power);
+            lower + upper <= u32::MAX as u128
+        })
+        .filter_map(|grouping_power| Config::new(grouping_power, max_value_power).ok())
+        .map(|config| {
+            (
+                config.grouping_power(),
+                config.error(),
+                config.total_buckets(),
+            )
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -228,6 +336,63 @@ mod tests {
         assert_eq!(config.total_buckets(), 12);
     }
 
+    #[test]
+    // Test that compatibility reports which parameter differs
+    fn compatible_with() {
+        let config = Config::new(7, 64).unwrap();
+        assert_eq!(config.compatible_with(&Config::new(7, 64).unwrap()), Ok(()));
+        assert_eq!(
+            config.compatible_with(&Config::new(6, 64).unwrap()),
+            Err(Error::IncompatibleGroupingPower { this: 7, other: 6 })
+        );
+        assert_eq!(
+            config.compatible_with(&Config::new(7, 32).unwrap()),
+            Err(Error::IncompatibleMaxValuePower {
+                this: 64,
+                other: 32
+            })
+        );
+        assert_eq!(
+            config.compatible_with(&Config::new(6, 32).unwrap()),
+            Err(Error::IncompatibleGroupingPower { this: 7, other: 6 })
+        );
+    }
+
+    #[test]
+    // Test that the trade-offs cover every grouping power and are monotonic
+    fn config_tradeoffs() {
+        let tradeoffs = super::config_tradeoffs(64);
+        assert_eq!(tradeoffs.len(), 27);
+        assert_eq!(tradeoffs[2], (2, 25.0, 252));
+        assert_eq!(tradeoffs[7], (7, 0.78125, 7424));
+
+        for pair in tradeoffs.windows(2) {
+            assert_eq!(pair[1].0, pair[0].0 + 1);
+            assert!(pair[1].1 < pair[0].1);
+            assert!(pair[1].2 > pair[0].2);
+        }
+
+        assert_eq!(super::config_tradeoffs(4).len(), 4);
+        assert!(super::config_tradeoffs(0).is_empty());
+        assert!(super::config_tradeoffs(65).is_empty());
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    // Test that random values are always within the storable range
+    fn random_values() {
+        let mut rng = rand::thread_rng();
+
+        for (grouping_power, max_value_power) in [(0, 1), (2, 4), (7, 32), (7, 64)] {
+            let config = Config::new(grouping_power, max_value_power).unwrap();
+            let mut histogram = crate::Histogram::with_config(&config);
+
+            for value in config.random_values(&mut rng, 10_000) {
+                assert_eq!(histogram.increment(value), Ok(()));
+            }
+        }
+    }
+
     #[test]
     // Test value to index conversions
     fn value_to_idx() {
@@ -288,4 +453,37 @@ mod tests {
             18_374_686_479_671_623_680..=u64::MAX
         );
     }
+
+    #[test]
+    // Test that the exported bucket bounds match the index to range conversion
+    fn bucket_bounds() {
+        let config = Config::new(7, 64).unwrap();
+        let bounds = config.bucket_bounds();
+        assert_eq!(bounds.len(), config.total_buckets());
+
+        for index in [0, 1, 256, 384, 512, 7423] {
+            assert_eq!(bounds[index], config.index_to_range(index));
+        }
+
+        assert!(config.bucket_bounds_iter().eq(bounds.into_iter()));
+
+        let config = Config::new(2, 4).unwrap();
+        assert_eq!(
+            config.bucket_bounds(),
+            vec![
+                0..=0,
+                1..=1,
+                2..=2,
+                3..=3,
+                4..=4,
+                5..=5,
+                6..=6,
+                7..=7,
+                8..=9,
+                10..=11,
+                12..=13,
+                14..=16
+            ]
+        );
+    }
 }
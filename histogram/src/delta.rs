@@ -0,0 +1,94 @@
+use crate::bucket::midpoint;
+use crate::{Error, Histogram};
+
+/// Compares the percentiles of two histograms, such as snapshots taken before
+/// and after a change, and returns how much each percentile shifted.
+///
+/// Each percentile is computed on both histograms and the difference is
+/// reported as `current - baseline`, using the midpoint of each bucket. A
+/// positive difference means the percentile increased. Differences which do
+/// not fit in an `i64` saturate.
+///
+/// Each percentile should be in the inclusive range `0.0..=100.0`. The results
+/// will be sorted by the percentile. If either histogram is empty, there is
+/// nothing to compare and the result is empty.
+///
+/// An error is returned if the two histograms have incompatible parameters or
+/// if any percentile is invalid.
+pub fn percentile_delta(
+    baseline: &Histogram,
+    current: &Histogram,
+    percentiles: &[f64],
+) -> Result<Vec<(f64, i64)>, Error> {
+    if baseline.config() != current.config() {
+        return Err(Error::IncompatibleParameters);
+    }
+
+    let (baseline, current) = match (
+        baseline.percentiles(percentiles)?,
+        current.percentiles(percentiles)?,
+    ) {
+        (Some(baseline), Some(current)) => (baseline, current),
+        _ => return Ok(Vec::new()),
+    };
+
+    Ok(baseline
+        .iter()
+        .zip(current.iter())
+        .map(|((percentile, baseline), (_, current))| {
+            let delta = midpoint(&current.range) as i128 - midpoint(&baseline.range) as i128;
+            (
+                *percentile,
+                delta.clamp(i64::MIN as i128, i64::MAX as i128) as i64,
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_delta() {
+        let mut baseline = Histogram::new(7, 64).unwrap();
+        let mut current = Histogram::new(7, 64).unwrap();
+
+        for value in 1..=100 {
+            let _ = baseline.increment(value);
+            let _ = current.increment(value + 50);
+        }
+
+        assert_eq!(
+            super::percentile_delta(&baseline, &current, &[50.0, 99.0, 1.0]),
+            Ok(vec![(1.0, 50), (50.0, 50), (99.0, 50)])
+        );
+        assert_eq!(
+            super::percentile_delta(&current, &baseline, &[50.0]),
+            Ok(vec![(50.0, -50)])
+        );
+
+        // the shift is measured between bucket midpoints
+        let _ = current.add(10_000, 100);
+        assert_eq!(
+            super::percentile_delta(&baseline, &current, &[99.0]),
+            Ok(vec![(99.0, 10_015 - 99)])
+        );
+
+        let empty = Histogram::new(7, 64).unwrap();
+        assert_eq!(
+            super::percentile_delta(&baseline, &empty, &[50.0]),
+            Ok(Vec::new())
+        );
+
+        let mismatch = Histogram::new(6, 64).unwrap();
+        assert_eq!(
+            super::percentile_delta(&baseline, &mismatch, &[50.0]),
+            Err(Error::IncompatibleParameters)
+        );
+        assert_eq!(
+            super::percentile_delta(&baseline, &current, &[101.0]),
+            Err(Error::InvalidPercentile)
+        );
+    }
+}
@@ -0,0 +1,16 @@
+/// The metric used to compare the distributions of two histograms.
+///
+/// Both metrics operate on the normalized per-bucket probabilities, where the
+/// probability of a bucket is its count divided by the total count of the
+/// histogram:
+/// * `TotalVariation` - half of the sum of the absolute differences between
+///   the bucket probabilities. The result is in the range `0.0..=1.0`.
+/// * `KullbackLeibler` - the Kullback-Leibler divergence, in nats, of one
+///   histogram from the other. Laplace smoothing (adding one to the count of
+///   every bucket) is applied so that the result is finite even when a bucket
+///   is empty in only one of the histograms. Note that this is not symmetric.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DistanceMetric {
+    TotalVariation,
+    KullbackLeibler,
+}
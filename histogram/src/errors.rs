@@ -10,16 +10,32 @@ pub enum Error {
     MaxPowerTooLow,
     #[error("invalid percentile, must be in range 0.0..=100.0")]
     InvalidPercentile,
+    #[error("invalid relative error, must be in range 0.0 < e <= 1.0")]
+    InvalidRelativeError,
+    #[error("invalid confidence, must be in range 0.0 < c < 1.0")]
+    InvalidConfidence,
+    #[error("invalid fraction, must be in range 0.0 < f <= 1.0")]
+    InvalidFraction,
+    #[error("the histogram is empty")]
+    Empty,
     #[error("the value is outside of the storable range")]
     OutOfRange,
     #[error("the histogram parameters are incompatible")]
     IncompatibleParameters,
+    #[error("the grouping powers are incompatible: {this} != {other}")]
+    IncompatibleGroupingPower { this: u8, other: u8 },
+    #[error("the max value powers are incompatible: {this} != {other}")]
+    IncompatibleMaxValuePower { this: u8, other: u8 },
     #[error("the snapshot time ranges do not allow this operation")]
     IncompatibleTimeRange,
     #[error("an overflow occurred")]
     Overflow,
     #[error("an underflow occurred")]
     Underflow,
+    #[error("an overflow occurred in bucket {index}")]
+    BucketOverflow { index: usize },
+    #[error("an underflow occurred in bucket {index}")]
+    BucketUnderflow { index: usize },
     #[error("the histogram is not a subset")]
     InvalidSubset,
 }
@@ -0,0 +1,66 @@
+use crate::bucket::midpoint;
+use crate::Histogram;
+
+impl Histogram {
+    /// Converts this histogram into an [`hdrhistogram::Histogram`] for interop
+    /// with consumers of the HdrHistogram ecosystem.
+    ///
+    /// The count for each bucket is recorded at the midpoint of the bucket's
+    /// range, using a number of significant digits which is comparable to the
+    /// grouping power of this histogram. Since the two histograms use different
+    /// bucket boundaries, this resampling shifts values within their original
+    /// buckets. The error of any value read back from the resulting histogram
+    /// is bounded by the width of the original bucket plus the relative error
+    /// of the HdrHistogram.
+    ///
+    /// Bucket counts which exceed the capacity of the HdrHistogram counters
+    /// saturate rather than wrap.
+    pub fn to_hdr(&self) -> hdrhistogram::Histogram<u64> {
+        // a relative error of 2^-grouping_power needs this many decimal digits
+        let sigfig = (self.config.grouping_power() as f64 * core::f64::consts::LOG10_2)
+            .ceil()
+            .clamp(1.0, 5.0) as u8;
+
+        let max = self
+            .config
+            .index_to_upper_bound(self.config.total_buckets() - 1)
+            .max(2);
+
+        let mut hdr = hdrhistogram::Histogram::new_with_bounds(1, max, sigfig)
+            .expect("bounds and significant digits are always valid");
+
+        for bucket in self.into_iter().filter(|bucket| bucket.count() > 0) {
+            hdr.saturating_record_n(midpoint(&bucket.range), bucket.count());
+        }
+
+        hdr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Histogram;
+
+    #[test]
+    fn to_hdr() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        for value in 1..=1000 {
+            let _ = histogram.increment(value);
+        }
+        let _ = histogram.add(1_000_000, 10);
+        let _ = histogram.increment(u64::MAX);
+
+        let hdr = histogram.to_hdr();
+        assert_eq!(hdr.len(), 1011);
+
+        // values are within the combined error of both histograms
+        let p50 = hdr.value_at_quantile(0.5);
+        assert!((p50 as f64 - 506.0).abs() / 506.0 < 0.02);
+        let p99 = hdr.value_at_quantile(0.99);
+        assert!((p99 as f64 - 1_000_000.0).abs() / 1_000_000.0 < 0.02);
+        assert!(hdr.max() > u64::MAX / 2);
+
+        let hdr = Histogram::new(2, 4).unwrap().to_hdr();
+        assert!(hdr.is_empty());
+    }
+}
@@ -12,15 +12,40 @@
 //! Please see: <https://observablehq.com/@iopsystems/h2histogram>
 
 mod atomic;
+mod averaged;
 mod bucket;
+mod collect;
 mod config;
+mod delta;
+mod distance;
 mod errors;
+mod rounding;
+mod signed;
 mod sparse;
 mod standard;
+mod summary;
+mod typed;
+
+#[cfg(feature = "serde")]
+pub mod serde_sparse;
+
+#[cfg(feature = "hdr")]
+mod hdr;
+
+#[cfg(feature = "svg")]
+mod sparkline;
 
 pub use atomic::AtomicHistogram;
+pub use averaged::AveragedHistogram;
 pub use bucket::Bucket;
-pub use config::Config;
+pub use collect::HistogramCollect;
+pub use config::{config_tradeoffs, Config};
+pub use delta::percentile_delta;
+pub use distance::DistanceMetric;
 pub use errors::Error;
+pub use rounding::Rounding;
+pub use signed::SignedHistogram;
 pub use sparse::SparseHistogram;
 pub use standard::Histogram;
+pub use summary::Summary;
+pub use typed::TypedHistogram;
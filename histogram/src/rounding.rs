@@ -0,0 +1,37 @@
+/// The rule used to convert a percentile into the rank of an observation.
+///
+/// For a percentile `p` in the range `0.0..=100.0` and a histogram with a
+/// total count of `n`, the rank is computed from the unrounded rank
+/// `r = p / 100.0 * n` as follows:
+/// * `Floor` - `rank = floor(r)`
+/// * `Ceil` - `rank = ceil(r)`
+/// * `Round` - `rank = round(r)`, with ties rounded away from zero
+/// * `Nearest` - `rank = round(r)`, with ties rounded to the nearest even rank
+///
+/// The rank is never less than one, so that the lowest percentiles select the
+/// first non-empty bucket. The selected bucket is the first bucket where the
+/// cumulative count is greater than or equal to the rank.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    Floor,
+    #[default]
+    Ceil,
+    Round,
+    Nearest,
+}
+
+impl Rounding {
+    /// Converts a percentile into a rank using this rounding rule.
+    pub(crate) fn rank(&self, percentile: f64, total: u128) -> u128 {
+        let rank = percentile / 100.0 * total as f64;
+
+        let rank = match self {
+            Self::Floor => rank.floor() as u128,
+            Self::Ceil => rank.ceil() as u128,
+            Self::Round => rank.round() as u128,
+            Self::Nearest => rank.round_ties_even() as u128,
+        };
+
+        rank.max(1)
+    }
+}
@@ -0,0 +1,89 @@
+//! Serialize a [`crate::Histogram`] using the sparse representation.
+//!
+//! By default, a `Histogram` serializes every bucket, including all of the
+//! buckets which are zero. For histograms where most buckets are zero, this
+//! produces much larger payloads than necessary. This module can be used with
+//! serde's `with` attribute to instead serialize the histogram as a
+//! [`crate::SparseHistogram`], which only includes the non-zero buckets.
+//!
+//! ```
+//! use histogram::Histogram;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Report {
+//!     // uses the sparse representation
+//!     #[serde(with = "histogram::serde_sparse")]
+//!     latency: Histogram,
+//!     // uses the default dense representation
+//!     size: Histogram,
+//! }
+//! ```
+//!
+//! The serialized form is identical to that of a `SparseHistogram`, so
+//! payloads can also be deserialized directly as a `SparseHistogram`.
+
+use crate::{Histogram, SparseHistogram};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Serialize the histogram using the sparse representation.
+pub fn serialize<S: Serializer>(histogram: &Histogram, serializer: S) -> Result<S::Ok, S::Error> {
+    SparseHistogram::from(histogram).serialize(serializer)
+}
+
+/// Deserialize a histogram from the sparse representation.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Histogram, D::Error> {
+    let sparse = SparseHistogram::deserialize(deserializer)?;
+
+    if sparse.index.len() != sparse.count.len() {
+        return Err(D::Error::custom("index and count lengths do not match"));
+    }
+
+    if sparse
+        .index
+        .iter()
+        .any(|index| *index >= sparse.config.total_buckets())
+    {
+        return Err(D::Error::custom("bucket index is out of range"));
+    }
+
+    Ok(Histogram::from(&sparse))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Histogram;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Wrapper {
+        #[serde(with = "crate::serde_sparse")]
+        histogram: Histogram,
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        let _ = histogram.add(1, 2);
+        let _ = histogram.add(1000, 3);
+
+        let wrapper = Wrapper { histogram };
+        let sparse = serde_json::to_string(&wrapper).unwrap();
+        let dense = serde_json::to_string(&wrapper.histogram).unwrap();
+        assert!(sparse.len() < dense.len());
+
+        let decoded: Wrapper = serde_json::from_str(&sparse).unwrap();
+        assert_eq!(decoded, wrapper);
+    }
+
+    #[test]
+    fn invalid() {
+        let mut histogram = Histogram::new(2, 4).unwrap();
+        let _ = histogram.increment(1);
+
+        let json = serde_json::to_string(&Wrapper { histogram }).unwrap();
+        let json = json.replace("\"index\":[1]", "\"index\":[100]");
+        assert!(serde_json::from_str::<Wrapper>(&json).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{Bucket, Config, Error, Histogram};
+use core::ops::RangeInclusive;
+
+/// A [`crate::Histogram`] which records signed values by applying a fixed
+/// offset.
+///
+/// Each value is stored as `value - offset` in the underlying histogram, so the
+/// range of values which can be recorded is `offset..=offset + 2^N - 1`, where
+/// `N` is the max value power, limited to the range of an `i64`. The offset is
+/// fixed when the histogram is constructed. Percentiles are translated back to
+/// signed values when read.
+///
+/// Note that the relative error of the histogram applies to the stored value,
+/// not the signed value. Values far above the offset are stored in wider
+/// buckets. For example, an offset of `i64::MIN` allows any `i64` to be
+/// recorded, but values near zero are then stored around `2^63` with a large
+/// absolute error. Choosing an offset just below the smallest expected value
+/// keeps the error small.
+///
+/// ```
+/// use histogram::SignedHistogram;
+///
+/// let mut histogram = SignedHistogram::new(7, 16, -100).unwrap();
+/// histogram.increment(-50).unwrap();
+/// histogram.increment(25).unwrap();
+///
+/// assert_eq!(histogram.percentile(0.0).unwrap(), Some(-50..=-50));
+/// assert_eq!(histogram.percentile(100.0).unwrap(), Some(25..=25));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedHistogram {
+    histogram: Histogram,
+    offset: i64,
+}
+
+impl SignedHistogram {
+    /// Construct a new histogram from the provided parameters and offset. See
+    /// the documentation for [`crate::Config`] to understand the meaning of the
+    /// parameters.
+    pub fn new(grouping_power: u8, max_value_power: u8, offset: i64) -> Result<Self, Error> {
+        let config = Config::new(grouping_power, max_value_power)?;
+
+        Ok(Self::with_config(&config, offset))
+    }
+
+    /// Creates a new histogram using a provided [`crate::Config`] and offset.
+    pub fn with_config(config: &Config, offset: i64) -> Self {
+        Self {
+            histogram: Histogram::with_config(config),
+            offset,
+        }
+    }
+
+    /// Returns the offset which is subtracted from each value before it is
+    /// stored.
+    pub fn offset(&self) -> i64 {
+        self.offset
+    }
+
+    /// Returns the underlying histogram, which holds the offset values.
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Increment the counter for the bucket corresponding to the provided value
+    /// by one.
+    ///
+    /// An error is returned if the value is outside of the storable range.
+    pub fn increment(&mut self, value: i64) -> Result<(), Error> {
+        self.add(value, 1)
+    }
+
+    /// Add some count to the counter for the bucket corresponding to the
+    /// provided value.
+    ///
+    /// An error is returned if the value is outside of the storable range.
+    pub fn add(&mut self, value: i64, count: u64) -> Result<(), Error> {
+        let value =
+            u64::try_from(value as i128 - self.offset as i128).map_err(|_| Error::OutOfRange)?;
+
+        self.histogram.add(value, count)
+    }
+
+    /// Return a single percentile from this histogram as the range of signed
+    /// values for the bucket which holds the percentile.
+    ///
+    /// The percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    pub fn percentile(&self, percentile: f64) -> Result<Option<RangeInclusive<i64>>, Error> {
+        Ok(self
+            .histogram
+            .percentile(percentile)?
+            .map(|bucket| self.to_signed(&bucket)))
+    }
+
+    /// Return a collection of percentiles from this histogram, each with the
+    /// range of signed values for the bucket which holds the percentile.
+    ///
+    /// Each percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    ///
+    /// The results will be sorted by the percentile.
+    #[allow(clippy::type_complexity)]
+    pub fn percentiles(
+        &self,
+        percentiles: &[f64],
+    ) -> Result<Option<Vec<(f64, RangeInclusive<i64>)>>, Error> {
+        Ok(self.histogram.percentiles(percentiles)?.map(|percentiles| {
+            percentiles
+                .into_iter()
+                .map(|(percentile, bucket)| (percentile, self.to_signed(&bucket)))
+                .collect()
+        }))
+    }
+
+    /// Internal function to translate the range of a bucket back to signed
+    /// values. Bounds beyond the range of an `i64` saturate.
+    fn to_signed(&self, bucket: &Bucket) -> RangeInclusive<i64> {
+        let translate = |value: u64| {
+            (value as i128 + self.offset as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+        };
+
+        translate(bucket.start())..=translate(bucket.end())
+    }
+}
+
+impl From<SignedHistogram> for Histogram {
+    fn from(signed: SignedHistogram) -> Self {
+        signed.histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed() {
+        let mut histogram = SignedHistogram::new(7, 64, i64::MIN).unwrap();
+        assert_eq!(histogram.percentile(50.0), Ok(None));
+
+        histogram.increment(i64::MIN).unwrap();
+        histogram.increment(-1).unwrap();
+        histogram.increment(i64::MAX).unwrap();
+
+        assert_eq!(histogram.percentile(0.0), Ok(Some(i64::MIN..=i64::MIN)));
+        assert_eq!(
+            histogram.percentile(100.0).unwrap().unwrap().end(),
+            &i64::MAX
+        );
+
+        // values near zero are in a wide bucket when the offset is far away
+        let median = histogram.percentile(50.0).unwrap().unwrap();
+        assert!(median.contains(&-1));
+        assert!(median.end() - median.start() > 1 << 50);
+    }
+
+    #[test]
+    fn offset() {
+        let mut histogram = SignedHistogram::new(7, 16, -100).unwrap();
+        assert_eq!(histogram.offset(), -100);
+
+        for value in -100..=100 {
+            histogram.increment(value).unwrap();
+        }
+
+        assert_eq!(
+            histogram.percentiles(&[0.0, 50.0, 100.0]),
+            Ok(Some(vec![
+                (0.0, -100..=-100),
+                (50.0, 0..=0),
+                (100.0, 100..=100)
+            ]))
+        );
+        assert_eq!(
+            histogram
+                .histogram()
+                .percentile(0.0)
+                .unwrap()
+                .unwrap()
+                .range(),
+            0..=0
+        );
+
+        // values outside of the range which can be stored are rejected
+        assert_eq!(histogram.increment(-101), Err(Error::OutOfRange));
+        assert_eq!(histogram.increment(100_000), Err(Error::OutOfRange));
+        assert_eq!(histogram.increment(65435), Ok(()));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::Histogram;
+use core::fmt::Write;
+
+impl Histogram {
+    /// Renders the shape of the histogram as a small SVG bar chart.
+    ///
+    /// Each non-zero bucket is drawn as a bar, from the lowest to the highest
+    /// bucket, with the bars evenly dividing the `width`. Bar heights use a
+    /// logarithmic scale so that buckets with small counts remain visible next
+    /// to the largest bucket, which always spans the full `height`.
+    ///
+    /// An empty histogram renders as an SVG with no bars.
+    pub fn sparkline_svg(&self, width: u32, height: u32) -> String {
+        let counts: Vec<u64> = self.buckets.iter().copied().filter(|c| *c > 0).collect();
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">"
+        );
+
+        if let Some(max) = counts.iter().max() {
+            let max = (*max as f64).ln_1p();
+            let bar_width = width as f64 / counts.len() as f64;
+
+            for (i, count) in counts.iter().enumerate() {
+                let bar_height = (*count as f64).ln_1p() / max * height as f64;
+
+                let _ = write!(
+                    svg,
+                    "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\"/>",
+                    i as f64 * bar_width,
+                    height as f64 - bar_height,
+                    bar_width,
+                    bar_height,
+                );
+            }
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Histogram;
+
+    #[test]
+    fn sparkline_svg() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+
+        let svg = histogram.sparkline_svg(100, 20);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>"));
+        assert!(!svg.contains("<rect"));
+
+        let _ = histogram.add(1, 1);
+        let _ = histogram.add(10, 100);
+        let _ = histogram.add(1000, 10);
+
+        let svg = histogram.sparkline_svg(90, 20);
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"90\" height=\"20\"")
+        );
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect ").count(), 3);
+        assert_eq!(svg.matches("/>").count(), 3);
+
+        // the largest bucket spans the full height
+        assert!(svg.contains("<rect x=\"30.00\" y=\"0.00\" width=\"30.00\" height=\"20.00\"/>"));
+    }
+}
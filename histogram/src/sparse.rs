@@ -1,4 +1,5 @@
-use crate::{Bucket, Config, Error, Histogram};
+use crate::{Bucket, Config, Error, Histogram, Rounding};
+use std::io::{Read, Write};
 
 /// This histogram is a sparse, columnar representation of the regular
 /// Histogram. It is significantly smaller than a regular Histogram
@@ -45,13 +46,14 @@ impl SparseHistogram {
         }
     }
 
-    /// Adds the other histogram to this histogram and returns the result as a
-    /// new histogram.
-    ///
-    /// An error is returned if the two histograms have incompatible parameters.
-    /// Buckets which have values in both histograms are allowed to wrap.
+    /// Helper function to merge the buckets of two histograms, combining the
+    /// counts of buckets present in both histograms with the provided function.
     #[allow(clippy::comparison_chain)]
-    pub fn wrapping_add(&self, h: &SparseHistogram) -> Result<SparseHistogram, Error> {
+    fn merge(
+        &self,
+        h: &SparseHistogram,
+        combine: impl Fn(usize, u64, u64) -> Result<u64, Error>,
+    ) -> Result<SparseHistogram, Error> {
         if self.config != h.config {
             return Err(Error::IncompatibleParameters);
         }
@@ -65,7 +67,7 @@ impl SparseHistogram {
             let (k2, v2) = (h.index[j], h.count[j]);
 
             if k1 == k2 {
-                histogram.add_bucket(k1, v1 + v2);
+                histogram.add_bucket(k1, combine(k1, v1, v2)?);
                 (i, j) = (i + 1, j + 1);
             } else if k1 < k2 {
                 histogram.add_bucket(k1, v1);
@@ -91,6 +93,27 @@ impl SparseHistogram {
         Ok(histogram)
     }
 
+    /// Adds the other histogram to this histogram and returns the result as a
+    /// new histogram.
+    ///
+    /// An error is returned if the two histograms have incompatible parameters
+    /// or if there is an overflow. On overflow, the error identifies the first
+    /// bucket which overflowed.
+    pub fn checked_add(&self, h: &SparseHistogram) -> Result<SparseHistogram, Error> {
+        self.merge(h, |index, v1, v2| {
+            v1.checked_add(v2).ok_or(Error::BucketOverflow { index })
+        })
+    }
+
+    /// Adds the other histogram to this histogram and returns the result as a
+    /// new histogram.
+    ///
+    /// An error is returned if the two histograms have incompatible parameters.
+    /// Buckets which have values in both histograms are allowed to wrap.
+    pub fn wrapping_add(&self, h: &SparseHistogram) -> Result<SparseHistogram, Error> {
+        self.merge(h, |_, v1, v2| Ok(v1.wrapping_add(v2)))
+    }
+
     /// Subtracts the other histogram to this histogram and returns the result as a
     /// new histogram. The other histogram is expected to be a subset of the current
     /// histogram, i.e., for every bucket in the other histogram should have a
@@ -113,7 +136,9 @@ impl SparseHistogram {
             let (k2, v2) = (h.index[j], h.count[j]);
 
             if k1 == k2 {
-                let v = v1.checked_sub(v2).ok_or(Error::Underflow)?;
+                let v = v1
+                    .checked_sub(v2)
+                    .ok_or(Error::BucketUnderflow { index: k1 })?;
                 if v != 0 {
                     histogram.add_bucket(k1, v);
                 }
@@ -171,16 +196,18 @@ impl SparseHistogram {
         let mut percentiles = percentiles.to_vec();
         percentiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
 
-        let searches: Vec<usize> = percentiles
+        // use the same rank calculation as the dense histogram so that both
+        // representations select the same buckets
+        let searches: Vec<u128> = percentiles
             .iter()
-            .map(|p| ((total as f64) * *p / 100.0).ceil() as usize)
+            .map(|p| Rounding::Ceil.rank(*p, total))
             .collect();
         let mut search_idx = 0;
         let mut result: Vec<(f64, Bucket)> = Vec::with_capacity(percentiles.len());
 
-        let mut seen: usize = 0;
+        let mut seen: u128 = 0;
         for (idx, count) in self.index.iter().zip(self.count.iter()) {
-            seen += *count as usize;
+            seen += *count as u128;
             while search_idx < searches.len() && seen >= searches[search_idx] {
                 result.push((
                     percentiles[search_idx],
@@ -252,6 +279,94 @@ impl SparseHistogram {
 
         Ok(histogram)
     }
+
+    /// Returns the number of bytes of heap memory allocated for the index and
+    /// count vectors.
+    pub fn heap_size(&self) -> usize {
+        self.index.capacity() * core::mem::size_of::<usize>()
+            + self.count.capacity() * core::mem::size_of::<u64>()
+    }
+
+    /// Writes the histogram to a writer using a compact binary framing. This
+    /// allows many histograms to be written to a stream and later decoded one
+    /// at a time with [`SparseHistogram::read_from`].
+    ///
+    /// The framing consists of the grouping power and max value power as one
+    /// byte each, followed by the indices as a length-prefixed sequence of
+    /// `u32` and the counts as a length-prefixed sequence of `u64`. The
+    /// lengths are `u32` and all integers are little-endian.
+    pub fn write_to(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writer.write_all(&[self.config.grouping_power(), self.config.max_value_power()])?;
+
+        writer.write_all(&(self.index.len() as u32).to_le_bytes())?;
+        for index in &self.index {
+            writer.write_all(&(*index as u32).to_le_bytes())?;
+        }
+
+        writer.write_all(&(self.count.len() as u32).to_le_bytes())?;
+        for count in &self.count {
+            writer.write_all(&count.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a single histogram from a reader which was written using
+    /// [`SparseHistogram::write_to`]. Only the bytes for this histogram are
+    /// consumed, so subsequent histograms can be read from the same reader.
+    ///
+    /// An error of kind [`std::io::ErrorKind::InvalidData`] is returned if the
+    /// parameters are invalid, the index and count lengths do not match, or
+    /// the indices are not increasing and within the range of the config.
+    pub fn read_from(reader: &mut impl Read) -> std::io::Result<Self> {
+        let mut params = [0; 2];
+        reader.read_exact(&mut params)?;
+
+        let config = Config::new(params[0], params[1]).map_err(invalid_data)?;
+
+        let len = read_u32(reader)? as usize;
+        if len > config.total_buckets() {
+            return Err(invalid_data("too many buckets for the config"));
+        }
+
+        let mut index = Vec::with_capacity(len);
+        for _ in 0..len {
+            let idx = read_u32(reader)? as usize;
+
+            if idx >= config.total_buckets() || index.last().is_some_and(|prev| *prev >= idx) {
+                return Err(invalid_data("bucket indices are out of range or order"));
+            }
+
+            index.push(idx);
+        }
+
+        if read_u32(reader)? as usize != len {
+            return Err(invalid_data("index and count lengths do not match"));
+        }
+
+        let mut count = Vec::with_capacity(len);
+        for _ in 0..len {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            count.push(u64::from_le_bytes(bytes));
+        }
+
+        Ok(Self {
+            config,
+            index,
+            count,
+        })
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
 }
 
 impl<'a> IntoIterator for &'a SparseHistogram {
@@ -355,6 +470,95 @@ mod tests {
         let h = h1.wrapping_add(&h3).unwrap();
         assert_eq!(h.index, vec![1, 2, 3, 5, 6, 11, 13]);
         assert_eq!(h.count, vec![6, 5, 19, 7, 3, 15, 6]);
+
+        // buckets which wrap to zero are omitted
+        let hmax = SparseHistogram {
+            config,
+            index: vec![3, 5],
+            count: vec![u64::MAX - 11, u64::MAX],
+        };
+        let h = h1.wrapping_add(&hmax).unwrap();
+        assert_eq!(h.index, vec![1, 5]);
+        assert_eq!(h.count, vec![6, 6]);
+    }
+
+    #[test]
+    fn read_write() {
+        let config = Config::new(7, 32).unwrap();
+
+        let h1 = SparseHistogram {
+            config,
+            index: vec![1, 3, 5],
+            count: vec![6, 12, u64::MAX],
+        };
+        let h2 = SparseHistogram::new(2, 64).unwrap();
+
+        let mut buffer = Vec::new();
+        h1.write_to(&mut buffer).unwrap();
+        h2.write_to(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), (2 + 4 + 3 * 4 + 4 + 3 * 8) + (2 + 4 + 4));
+
+        let mut cursor = std::io::Cursor::new(&buffer);
+        assert_eq!(SparseHistogram::read_from(&mut cursor).unwrap(), h1);
+        assert_eq!(SparseHistogram::read_from(&mut cursor).unwrap(), h2);
+        assert_eq!(
+            SparseHistogram::read_from(&mut cursor).unwrap_err().kind(),
+            std::io::ErrorKind::UnexpectedEof
+        );
+
+        // invalid parameters
+        let mut cursor = std::io::Cursor::new([7, 7, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            SparseHistogram::read_from(&mut cursor).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+
+        // indices out of order
+        let h3 = SparseHistogram {
+            config,
+            index: vec![3, 1],
+            count: vec![1, 1],
+        };
+        let mut buffer = Vec::new();
+        h3.write_to(&mut buffer).unwrap();
+        assert_eq!(
+            SparseHistogram::read_from(&mut buffer.as_slice())
+                .unwrap_err()
+                .kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn checked_add() {
+        let config = Config::new(7, 32).unwrap();
+
+        let h1 = SparseHistogram {
+            config,
+            index: vec![1, 3, 5],
+            count: vec![6, 12, 7],
+        };
+
+        let hdiff = SparseHistogram::new(6, 16).unwrap();
+        let h = h1.checked_add(&hdiff);
+        assert_eq!(h, Err(Error::IncompatibleParameters));
+
+        let hnear = SparseHistogram {
+            config,
+            index: vec![2, 3, 5],
+            count: vec![u64::MAX, u64::MAX - 12, u64::MAX - 7],
+        };
+        let h = h1.checked_add(&hnear).unwrap();
+        assert_eq!(h.index, vec![1, 2, 3, 5]);
+        assert_eq!(h.count, vec![6, u64::MAX, u64::MAX, u64::MAX]);
+
+        let hover = SparseHistogram {
+            config,
+            index: vec![2, 3, 5],
+            count: vec![u64::MAX, u64::MAX - 12, u64::MAX - 6],
+        };
+        let h = h1.checked_add(&hover);
+        assert_eq!(h, Err(Error::BucketOverflow { index: 5 }));
     }
 
     #[test]
@@ -387,7 +591,7 @@ mod tests {
             count: vec![4, 13, 7],
         };
         let h = h1.checked_sub(&hlarger);
-        assert_eq!(h, Err(Error::Underflow));
+        assert_eq!(h, Err(Error::BucketUnderflow { index: 3 }));
 
         let hmore = SparseHistogram {
             config,
@@ -415,6 +619,22 @@ mod tests {
         assert_eq!(h.count, vec![1, 3, 7]);
     }
 
+    #[test]
+    fn heap_size() {
+        let config = Config::new(7, 32).unwrap();
+        assert_eq!(SparseHistogram::with_config(&config).heap_size(), 0);
+
+        let histogram = SparseHistogram {
+            config,
+            index: vec![1, 3, 5],
+            count: vec![6, 12, 7],
+        };
+        assert_eq!(
+            histogram.heap_size(),
+            3 * std::mem::size_of::<usize>() + 3 * std::mem::size_of::<u64>()
+        );
+    }
+
     #[test]
     fn percentiles() {
         let mut hstandard = Histogram::new(4, 10).unwrap();
@@ -442,6 +662,62 @@ mod tests {
         );
     }
 
+    #[test]
+    // Tests that the dense and sparse histograms agree at boundary ranks
+    fn percentiles_boundaries() {
+        let percentiles: Vec<f64> = (0..=1000).map(|p| p as f64 / 10.0).collect();
+
+        // leading and interior empty buckets, and cumulative counts which land
+        // exactly on percentile ranks
+        let mut hstandard = Histogram::new(4, 10).unwrap();
+        let _ = hstandard.add(5, 10);
+        let _ = hstandard.add(100, 40);
+        let _ = hstandard.add(1000, 50);
+
+        let hsparse = SparseHistogram::from(&hstandard);
+        assert_eq!(
+            hstandard.percentiles(&percentiles).unwrap(),
+            hsparse.percentiles(&percentiles).unwrap()
+        );
+
+        // the lowest percentile is the first non-empty bucket
+        let expected = Bucket {
+            count: 10,
+            range: 5..=5,
+        };
+        assert_eq!(hstandard.percentile(0.0), Ok(Some(expected.clone())));
+        assert_eq!(hsparse.percentile(0.0), Ok(Some(expected.clone())));
+        assert_eq!(hstandard.percentile_bp(0), Ok(Some(expected.clone())));
+        assert_eq!(hstandard.percentiles_array([0.0]), Ok(Some([expected])));
+
+        // exactly on a cumulative boundary selects the lower bucket
+        assert_eq!(hsparse.percentile(10.0).unwrap().unwrap().range(), 5..=5);
+        assert_eq!(
+            hsparse.percentile(50.0).unwrap().unwrap().range(),
+            100..=103
+        );
+        assert_eq!(
+            hsparse.percentile(50.1).unwrap().unwrap().range(),
+            992..=1024
+        );
+
+        // the floating point rank is computed identically for both, even
+        // where it is not exact
+        let mut hstandard = Histogram::new(7, 64).unwrap();
+        let _ = hstandard.add(1, 999);
+        let _ = hstandard.increment(1000);
+
+        let hsparse = SparseHistogram::from(&hstandard);
+        assert_eq!(
+            hstandard.percentiles(&percentiles).unwrap(),
+            hsparse.percentiles(&percentiles).unwrap()
+        );
+        assert_eq!(
+            hstandard.percentile(99.9).unwrap(),
+            hsparse.percentile(99.9).unwrap()
+        );
+    }
+
     fn compare_histograms(hstandard: &Histogram, hsparse: &SparseHistogram) {
         assert_eq!(hstandard.config(), hsparse.config);
 
@@ -1,6 +1,11 @@
-use crate::{Bucket, Config, Error, SparseHistogram};
+use crate::bucket::midpoint;
+use crate::{Bucket, Config, DistanceMetric, Error, Rounding, SparseHistogram, Summary};
+use core::ops::RangeInclusive;
 
 /// A histogram that uses plain 64bit counters for each bucket.
+///
+/// With the `serde` feature enabled, the histogram serializes every bucket.
+/// See the `serde_sparse` module to serialize only the non-zero buckets instead.
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Histogram {
@@ -17,6 +22,32 @@ impl Histogram {
         Ok(Self::with_config(&config))
     }
 
+    /// Construct a new histogram which can store values up to `max_value` with
+    /// at most the provided relative error, for example `0.01` for 1%.
+    ///
+    /// The parameters are derived as follows:
+    /// * `max_value_power` is the number of bits needed to represent
+    ///   `max_value`, which is the smallest power where `max_value` is less
+    ///   than `2^max_value_power`.
+    /// * `grouping_power = ceil(-log2(relative_error))`.
+    ///
+    /// For example, a `max_value` of one billion with a relative error of 1%
+    /// results in a `grouping_power` of 7 and a `max_value_power` of 30. See
+    /// [`crate::Config`] for the meaning of the parameters.
+    ///
+    /// An error is returned if the relative error is not in the range
+    /// `0.0 < e <= 1.0`, or if the resulting parameters are invalid.
+    pub fn for_range(max_value: u64, relative_error: f64) -> Result<Self, Error> {
+        if !(relative_error > 0.0 && relative_error <= 1.0) {
+            return Err(Error::InvalidRelativeError);
+        }
+
+        let grouping_power = (-relative_error.log2()).ceil().min(u8::MAX as f64) as u8;
+        let max_value_power = (64 - max_value.leading_zeros()) as u8;
+
+        Self::new(grouping_power, max_value_power)
+    }
+
     /// Creates a new histogram using a provided [`crate::Config`].
     pub fn with_config(config: &Config) -> Self {
         let buckets: Box<[u64]> = vec![0; config.total_buckets()].into();
@@ -27,6 +58,36 @@ impl Histogram {
         }
     }
 
+    /// Construct a new histogram from the provided parameters, touching every
+    /// page of the bucket storage so that it is resident in memory. See the
+    /// documentation for [`crate::Config`] to understand the meaning of the
+    /// parameters.
+    ///
+    /// The storage for a new histogram is zeroed memory which the operating
+    /// system may only back with physical pages when each page is first
+    /// written. For large configs, this means early increments can page fault
+    /// and cause latency jitter. This trades a higher cost at construction for
+    /// avoiding those faults on the hot path.
+    pub fn new_prefaulted(grouping_power: u8, max_value_power: u8) -> Result<Self, Error> {
+        // the smallest common page size, touching at this stride also touches
+        // every page when the pages are larger
+        const PAGE_SIZE: usize = 4096;
+
+        let mut histogram = Self::new(grouping_power, max_value_power)?;
+
+        // write through `black_box` so the store to zeroed memory is not
+        // optimized away
+        for bucket in histogram
+            .buckets
+            .iter_mut()
+            .step_by(PAGE_SIZE / core::mem::size_of::<u64>())
+        {
+            *bucket = core::hint::black_box(0);
+        }
+
+        Ok(histogram)
+    }
+
     /// Creates a new histogram using a provided [`crate::Config`] and the
     /// provided collection of buckets.
     pub fn from_buckets(
@@ -52,6 +113,24 @@ impl Histogram {
         self.add(value, 1)
     }
 
+    /// Increment the counter for the bucket corresponding to the provided value
+    /// by one, without checking that the value is within the storable range.
+    ///
+    /// This is intended for hot loops where the caller has already validated
+    /// its values, and avoids the range check performed by `increment`.
+    ///
+    /// # Contract
+    /// The caller must ensure that the value is no larger than the max value
+    /// of the histogram's config. This is checked with a debug assertion. In
+    /// release builds, an out-of-range value will either panic or be counted
+    /// in the wrong bucket. Memory safety is never violated. For configs with
+    /// a `max_value_power` of 64, every value is in range.
+    #[inline]
+    pub fn increment_unchecked(&mut self, value: u64) {
+        let index = self.config.value_to_index_unchecked(value);
+        self.buckets[index] = self.buckets[index].wrapping_add(1);
+    }
+
     /// Add some count to the counter for the bucket corresponding to the
     /// provided value
     pub fn add(&mut self, value: u64, count: u64) -> Result<(), Error> {
@@ -60,6 +139,19 @@ impl Histogram {
         Ok(())
     }
 
+    /// Add some count to the counter for the bucket corresponding to the
+    /// provided value, saturating at `u64::MAX`.
+    ///
+    /// Unlike [`Histogram::add`], which wraps the bucket on overflow and can
+    /// leave it with a small count, this clamps the bucket at `u64::MAX`. This
+    /// is usually a safer choice for long-running accumulators, where a
+    /// wrapped bucket would silently corrupt the percentiles.
+    pub fn add_saturating_count(&mut self, value: u64, count: u64) -> Result<(), Error> {
+        let index = self.config.value_to_index(value)?;
+        self.buckets[index] = self.buckets[index].saturating_add(count);
+        Ok(())
+    }
+
     /// Get a reference to the raw counters.
     pub fn as_slice(&self) -> &[u64] {
         &self.buckets
@@ -77,8 +169,54 @@ impl Histogram {
     ///
     /// The results will be sorted by the percentile.
     pub fn percentiles(&self, percentiles: &[f64]) -> Result<Option<Vec<(f64, Bucket)>>, Error> {
+        self.percentiles_with(percentiles, Rounding::Ceil)
+    }
+
+    /// Return a collection of percentiles from this histogram, using the
+    /// provided [`crate::Rounding`] rule to convert each percentile into a
+    /// rank.
+    ///
+    /// Each percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    ///
+    /// The results will be sorted by the percentile.
+    pub fn percentiles_with(
+        &self,
+        percentiles: &[f64],
+        rounding: Rounding,
+    ) -> Result<Option<Vec<(f64, Bucket)>>, Error> {
+        self.percentiles_from(percentiles, rounding, 0)
+    }
+
+    /// Return a collection of percentiles from this histogram, ignoring any
+    /// observations of the value zero.
+    ///
+    /// This is useful when zero is used as a sentinel for "no measurement".
+    /// Only the bucket which holds exactly the value zero is excluded from both
+    /// the total count and the search. All other values, including any others
+    /// in the linear range of the histogram, are included as normal.
+    ///
+    /// Each percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    ///
+    /// The results will be sorted by the percentile.
+    pub fn percentiles_excluding_zero(
+        &self,
+        percentiles: &[f64],
+    ) -> Result<Option<Vec<(f64, Bucket)>>, Error> {
+        self.percentiles_from(percentiles, Rounding::Ceil, 1)
+    }
+
+    /// Internal function to calculate percentiles using only the buckets
+    /// starting at the `first` bucket index.
+    fn percentiles_from(
+        &self,
+        percentiles: &[f64],
+        rounding: Rounding,
+        first: usize,
+    ) -> Result<Option<Vec<(f64, Bucket)>>, Error> {
         // get the total count
-        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+        let total_count: u128 = self.buckets[first..].iter().map(|v| *v as u128).sum();
 
         // sort the requested percentiles so we can find them in a single pass
         let mut percentiles = percentiles.to_vec();
@@ -96,13 +234,13 @@ impl Histogram {
             return Ok(None);
         }
 
-        let mut bucket_idx = 0;
+        let mut bucket_idx = first;
         let mut partial_sum = self.buckets[bucket_idx] as u128;
 
         let result: Vec<(f64, Bucket)> = percentiles
             .iter()
             .filter_map(|percentile| {
-                let count = (percentile / 100.0 * total_count as f64).ceil() as u128;
+                let count = rounding.rank(*percentile, total_count);
 
                 loop {
                     // found the matching bucket index for this percentile
@@ -133,6 +271,136 @@ impl Histogram {
         Ok(Some(result))
     }
 
+    /// Return a summary of this histogram, with the total count, min, max,
+    /// mean, and the requested percentiles. This is more efficient than
+    /// computing each statistic separately, as all the statistics are computed
+    /// together in a single walk over the buckets. See [`crate::Summary`] for
+    /// how each statistic is computed.
+    ///
+    /// Each percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    ///
+    /// Returns `None` if the histogram is empty.
+    pub fn summary(&self, percentiles: &[f64]) -> Result<Option<Summary>, Error> {
+        // sort the requested percentiles so we can find them in a single pass
+        let mut percentiles = percentiles.to_vec();
+        percentiles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // validate all the percentiles
+        for percentile in &percentiles {
+            if !(0.0..=100.0).contains(percentile) {
+                return Err(Error::InvalidPercentile);
+            }
+        }
+
+        // get the total count, which is needed to convert percentiles to ranks
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        // empty histogram, no summary available
+        if total_count == 0 {
+            return Ok(None);
+        }
+
+        let mut ranks = percentiles
+            .iter()
+            .map(|percentile| (*percentile, Rounding::Ceil.rank(*percentile, total_count)))
+            .peekable();
+        let mut result = Vec::with_capacity(percentiles.len());
+
+        let mut partial_sum = 0;
+        let mut sum = 0.0;
+        let mut min = None;
+        let mut max = 0;
+
+        for (index, n) in self.buckets.iter().enumerate() {
+            if *n == 0 {
+                continue;
+            }
+
+            let range = self.config.index_to_range(index);
+
+            partial_sum += *n as u128;
+            sum += midpoint(&range) as f64 * *n as f64;
+            min.get_or_insert(*range.start());
+            max = *range.end();
+
+            // record every percentile whose rank falls within this bucket
+            while let Some((percentile, _)) = ranks.next_if(|(_, rank)| partial_sum >= *rank) {
+                result.push((
+                    percentile,
+                    Bucket {
+                        count: *n,
+                        range: range.clone(),
+                    },
+                ));
+            }
+        }
+
+        Ok(Some(Summary {
+            count: total_count,
+            min: min.unwrap_or(0),
+            max,
+            mean: sum / total_count as f64,
+            percentiles: result,
+        }))
+    }
+
+    /// Return a fixed-size collection of percentiles from this histogram
+    /// without allocating.
+    ///
+    /// Each percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    ///
+    /// Unlike [`Histogram::percentiles`], the results are in the same order as
+    /// the requested percentiles.
+    pub fn percentiles_array<const N: usize>(
+        &self,
+        percentiles: [f64; N],
+    ) -> Result<Option<[Bucket; N]>, Error> {
+        // validate all the percentiles
+        for percentile in &percentiles {
+            if !(0.0..=100.0).contains(percentile) {
+                return Err(Error::InvalidPercentile);
+            }
+        }
+
+        // get the total count
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        // empty histogram, no percentiles available
+        if total_count == 0 {
+            return Ok(None);
+        }
+
+        // sort the positions of the requested percentiles so we can find them
+        // in a single pass
+        let mut order: [usize; N] = core::array::from_fn(|i| i);
+        order.sort_unstable_by(|a, b| percentiles[*a].partial_cmp(&percentiles[*b]).unwrap());
+
+        let mut indices = [0; N];
+
+        let mut bucket_idx = 0;
+        let mut partial_sum = self.buckets[bucket_idx] as u128;
+
+        for position in order {
+            let count = Rounding::Ceil.rank(percentiles[position], total_count);
+
+            // advance until we find the matching bucket index for this
+            // percentile or reach the end of the buckets
+            while partial_sum < count && bucket_idx < (self.buckets.len() - 1) {
+                bucket_idx += 1;
+                partial_sum += self.buckets[bucket_idx] as u128;
+            }
+
+            indices[position] = bucket_idx;
+        }
+
+        Ok(Some(core::array::from_fn(|i| Bucket {
+            count: self.buckets[indices[i]],
+            range: self.config.index_to_range(indices[i]),
+        })))
+    }
+
     /// Return a single percentile from this histogram.
     ///
     /// The percentile should be in the inclusive range `0.0..=100.0`. For
@@ -142,6 +410,221 @@ impl Histogram {
             .map(|v| v.map(|x| x.first().unwrap().1.clone()))
     }
 
+    /// Return a collection of percentiles from this histogram, returning an
+    /// error instead of `None` if the histogram is empty.
+    ///
+    /// This behaves like [`Histogram::percentiles`], but reports an empty
+    /// histogram as [`crate::Error::Empty`], so that all cases without a
+    /// result can be handled as errors.
+    pub fn percentiles_or_err(&self, percentiles: &[f64]) -> Result<Vec<(f64, Bucket)>, Error> {
+        self.percentiles(percentiles)?.ok_or(Error::Empty)
+    }
+
+    /// Return a single percentile from this histogram, returning an error
+    /// instead of `None` if the histogram is empty.
+    ///
+    /// This behaves like [`Histogram::percentile`], but reports an empty
+    /// histogram as [`crate::Error::Empty`], so that all cases without a
+    /// result can be handled as errors.
+    pub fn percentile_or_err(&self, percentile: f64) -> Result<Bucket, Error> {
+        self.percentile(percentile)?.ok_or(Error::Empty)
+    }
+
+    /// Return a single percentile from this histogram along with the fraction
+    /// of all observations which are in the bucket for that percentile.
+    ///
+    /// A large fraction means many observations share the percentile's bucket,
+    /// while a small fraction means the observations around the percentile are
+    /// spread out.
+    ///
+    /// The percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    pub fn percentile_with_mass(&self, percentile: f64) -> Result<Option<(Bucket, f64)>, Error> {
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        Ok(self.percentile(percentile)?.map(|bucket| {
+            let mass = bucket.count() as f64 / total_count as f64;
+            (bucket, mass)
+        }))
+    }
+
+    /// Return the range of values which holds the central fraction of the
+    /// observations in this histogram. This is the range from the lower bound
+    /// of the `(1 - fraction) / 2` percentile's bucket to the upper bound of
+    /// the `(1 + fraction) / 2` percentile's bucket. For example, a fraction of
+    /// `0.8` gives the range from the 10th to the 90th percentile.
+    ///
+    /// The fraction should be in the range `0.0 < fraction <= 1.0`.
+    pub fn central_interval(&self, fraction: f64) -> Result<Option<RangeInclusive<u64>>, Error> {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            return Err(Error::InvalidFraction);
+        }
+
+        let lower = (1.0 - fraction) / 2.0 * 100.0;
+        let upper = (1.0 + fraction) / 2.0 * 100.0;
+
+        Ok(self
+            .percentiles(&[lower, upper])?
+            .map(|percentiles| percentiles[0].1.start()..=percentiles[1].1.end()))
+    }
+
+    /// Returns the smallest step between percentiles, in percent, which can
+    /// distinguish individual observations. This is `100.0 / total_count`.
+    ///
+    /// Percentiles which are closer together than this may select the same
+    /// observation. For example, with 50 observations the resolution is `2.0`,
+    /// so a request for p99.9 cannot be distinguished from p98. This can be
+    /// used to avoid reporting tail percentiles which the data does not
+    /// support.
+    ///
+    /// For an empty histogram, no percentile can be distinguished, and the
+    /// result is `f64::INFINITY`.
+    pub fn effective_resolution(&self) -> f64 {
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        100.0 / total_count as f64
+    }
+
+    /// Return the index of the bucket which a single percentile falls into,
+    /// without computing the range of the bucket. This selects the same bucket
+    /// as [`Histogram::percentile`].
+    ///
+    /// The percentile should be in the inclusive range `0.0..=100.0`. For
+    /// example, the 50th percentile (median) can be found using `50.0`.
+    pub fn percentile_index(&self, percentile: f64) -> Result<Option<usize>, Error> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(Error::InvalidPercentile);
+        }
+
+        // get the total count
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        // empty histogram, no percentiles available
+        if total_count == 0 {
+            return Ok(None);
+        }
+
+        let rank = Rounding::Ceil.rank(percentile, total_count);
+
+        Ok(Some(self.rank_to_index(rank)))
+    }
+
+    /// Internal function to find the index of the first bucket where the
+    /// cumulative count is greater than or equal to the rank. The rank must not
+    /// exceed the total count.
+    fn rank_to_index(&self, rank: u128) -> usize {
+        let mut partial_sum = 0;
+
+        for (idx, count) in self.buckets.iter().enumerate() {
+            partial_sum += *count as u128;
+
+            if partial_sum >= rank {
+                return idx;
+            }
+        }
+
+        // the rank never exceeds the total count, so a bucket is always found
+        unreachable!()
+    }
+
+    /// Return a confidence interval for a single percentile from this
+    /// histogram, accounting for the uncertainty due to the finite number of
+    /// observations. The result is the range of values from the lower bound of
+    /// the bucket holding the lowest rank in the interval to the upper bound of
+    /// the bucket holding the highest rank in the interval.
+    ///
+    /// The interval on the rank is computed using the normal approximation to
+    /// the binomial distribution: for `n` observations and a quantile `q`, the
+    /// rank is `n * q +/- z * sqrt(n * q * (1 - q))`, where `z` is the standard
+    /// normal quantile for the confidence level. This assumes the observations
+    /// are independent samples from the same distribution and is less accurate
+    /// for small samples or for percentiles close to `0.0` or `100.0`.
+    ///
+    /// The percentile should be in the inclusive range `0.0..=100.0` and the
+    /// confidence in the exclusive range `0.0..1.0`. For example, a 95%
+    /// confidence interval for the median can be found using `50.0` and
+    /// `0.95`.
+    pub fn percentile_ci(
+        &self,
+        percentile: f64,
+        confidence: f64,
+    ) -> Result<Option<(u64, u64)>, Error> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(Error::InvalidPercentile);
+        }
+
+        if !(confidence > 0.0 && confidence < 1.0) {
+            return Err(Error::InvalidConfidence);
+        }
+
+        // get the total count
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        // empty histogram, no percentiles available
+        if total_count == 0 {
+            return Ok(None);
+        }
+
+        let n = total_count as f64;
+        let q = percentile / 100.0;
+        let z = normal_quantile((1.0 - confidence) / 2.0);
+        let spread = z * (n * q * (1.0 - q)).sqrt();
+
+        // negative ranks saturate to zero when converted
+        let lower = ((n * q - spread).floor() as u128).clamp(1, total_count);
+        let upper = ((n * q + spread).ceil() as u128).clamp(1, total_count);
+
+        Ok(Some((
+            self.config.index_to_lower_bound(self.rank_to_index(lower)),
+            self.config.index_to_upper_bound(self.rank_to_index(upper)),
+        )))
+    }
+
+    /// Return a single percentile from this histogram, where the percentile is
+    /// expressed as an integer number of ten-thousandths of a percent.
+    ///
+    /// The argument must be in the inclusive range `0..=1_000_000`, where
+    /// `1_000_000` is the 100th percentile. For example, the 99.99th percentile
+    /// is `999_900` and the 99.9999th percentile is `999_999`.
+    ///
+    /// The rank is computed using integer arithmetic, rounding up in the same
+    /// way as [`Histogram::percentile`]. This avoids the floating point error
+    /// which can select the wrong bucket for extreme tail percentiles or large
+    /// total counts, and gives exact, reproducible results.
+    pub fn percentile_bp(&self, basis_points: u32) -> Result<Option<Bucket>, Error> {
+        if basis_points > 1_000_000 {
+            return Err(Error::InvalidPercentile);
+        }
+
+        // get the total count
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        // empty histogram, no percentiles available
+        if total_count == 0 {
+            return Ok(None);
+        }
+
+        let rank = (basis_points as u128 * total_count)
+            .div_ceil(1_000_000)
+            .max(1);
+
+        let mut partial_sum = 0;
+
+        for (idx, count) in self.buckets.iter().enumerate() {
+            partial_sum += *count as u128;
+
+            if partial_sum >= rank {
+                return Ok(Some(Bucket {
+                    count: *count,
+                    range: self.config.index_to_range(idx),
+                }));
+            }
+        }
+
+        // the rank never exceeds the total count, so a bucket is always found
+        unreachable!()
+    }
+
     /// Returns a new histogram with a reduced grouping power. The reduced
     /// grouping power should lie in the range (0..existing grouping power).
     ///
@@ -176,7 +659,8 @@ impl Histogram {
     /// new histogram.
     ///
     /// An error is returned if the two histograms have incompatible parameters
-    /// or if there is an overflow.
+    /// or if there is an overflow. On overflow, the error identifies the first
+    /// bucket which overflowed.
     pub fn checked_add(&self, other: &Histogram) -> Result<Histogram, Error> {
         if self.config != other.config {
             return Err(Error::IncompatibleParameters);
@@ -184,8 +668,15 @@ impl Histogram {
 
         let mut result = self.clone();
 
-        for (this, other) in result.buckets.iter_mut().zip(other.buckets.iter()) {
-            *this = this.checked_add(*other).ok_or(Error::Overflow)?;
+        for (index, (this, other)) in result
+            .buckets
+            .iter_mut()
+            .zip(other.buckets.iter())
+            .enumerate()
+        {
+            *this = this
+                .checked_add(*other)
+                .ok_or(Error::BucketOverflow { index })?;
         }
 
         Ok(result)
@@ -209,11 +700,111 @@ impl Histogram {
         Ok(result)
     }
 
+    /// Adds the other histogram to this histogram and returns the result as a
+    /// new histogram, allowing the histograms to have different max value
+    /// powers.
+    ///
+    /// With the same grouping power, the buckets of the histogram with the
+    /// smaller max value power are identical to the lower buckets of the
+    /// histogram with the larger max value power. The result uses the
+    /// configuration with the larger max value power.
+    ///
+    /// An error is returned if the two histograms have different grouping
+    /// powers or if there is an overflow.
+    pub fn add_extended(&self, other: &Histogram) -> Result<Histogram, Error> {
+        if self.config.grouping_power() != other.config.grouping_power() {
+            return Err(Error::IncompatibleParameters);
+        }
+
+        let (mut result, other) = if self.config.max_value_power() >= other.config.max_value_power()
+        {
+            (self.clone(), other)
+        } else {
+            (other.clone(), self)
+        };
+
+        for (index, (this, other)) in result
+            .buckets
+            .iter_mut()
+            .zip(other.buckets.iter())
+            .enumerate()
+        {
+            *this = this
+                .checked_add(*other)
+                .ok_or(Error::BucketOverflow { index })?;
+        }
+
+        Ok(result)
+    }
+
+    /// Adds the other histogram into this histogram in place.
+    ///
+    /// An error is returned if the two histograms have incompatible parameters
+    /// or if there is an overflow. This histogram is left unmodified if an
+    /// error is returned.
+    pub fn add_assign_checked(&mut self, other: &Histogram) -> Result<(), Error> {
+        if self.config != other.config {
+            return Err(Error::IncompatibleParameters);
+        }
+
+        // check for overflow before modifying any buckets
+        for (index, (this, other)) in self.buckets.iter().zip(other.buckets.iter()).enumerate() {
+            this.checked_add(*other)
+                .ok_or(Error::BucketOverflow { index })?;
+        }
+
+        for (this, other) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *this += *other;
+        }
+
+        Ok(())
+    }
+
+    /// Adds the other histogram into this histogram in place.
+    ///
+    /// An error is returned if the two histograms have incompatible parameters.
+    pub fn add_assign_wrapping(&mut self, other: &Histogram) -> Result<(), Error> {
+        if self.config != other.config {
+            return Err(Error::IncompatibleParameters);
+        }
+
+        for (this, other) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *this = this.wrapping_add(*other);
+        }
+
+        Ok(())
+    }
+
+    /// Adds raw bucket counts into this histogram in place. The counts must use
+    /// the same bucketing as this histogram, with one count for every bucket
+    /// in index order. Buckets are allowed to wrap.
+    ///
+    /// This is intended for hot aggregation loops, and is structured so that
+    /// the compiler can vectorize the additions.
+    ///
+    /// An error is returned if the number of counts does not match the number
+    /// of buckets.
+    pub fn add_slice_counts(&mut self, counts: &[u64]) -> Result<(), Error> {
+        if counts.len() != self.buckets.len() {
+            return Err(Error::IncompatibleParameters);
+        }
+
+        // slices of a known equal length allow bounds checks to be elided
+        let buckets = &mut self.buckets[..counts.len()];
+
+        for (this, other) in buckets.iter_mut().zip(counts) {
+            *this = this.wrapping_add(*other);
+        }
+
+        Ok(())
+    }
+
     /// Subtracts the other histogram from this histogram and returns the result
     /// as a new histogram.
     ///
     /// An error is returned if the two histograms have incompatible parameters
-    /// or if there is an overflow.
+    /// or if there is an underflow. On underflow, the error identifies the
+    /// first bucket which underflowed.
     pub fn checked_sub(&self, other: &Histogram) -> Result<Histogram, Error> {
         if self.config != other.config {
             return Err(Error::IncompatibleParameters);
@@ -221,8 +812,15 @@ impl Histogram {
 
         let mut result = self.clone();
 
-        for (this, other) in result.buckets.iter_mut().zip(other.buckets.iter()) {
-            *this = this.checked_sub(*other).ok_or(Error::Overflow)?;
+        for (index, (this, other)) in result
+            .buckets
+            .iter_mut()
+            .zip(other.buckets.iter())
+            .enumerate()
+        {
+            *this = this
+                .checked_sub(*other)
+                .ok_or(Error::BucketUnderflow { index })?;
         }
 
         Ok(result)
@@ -246,18 +844,222 @@ impl Histogram {
         Ok(result)
     }
 
-    /// Returns the bucket configuration of the histogram.
-    pub fn config(&self) -> Config {
-        self.config
-    }
-}
+    /// Multiplies the count of every bucket by a factor and returns the result
+    /// as a new histogram. This can be used to reconstruct a population from a
+    /// sampled histogram, where each observation represents `factor` events.
+    ///
+    /// Scaling by a non-zero factor does not change the percentiles. An error
+    /// is returned if there is an overflow, identifying the first bucket which
+    /// overflowed.
+    pub fn checked_scale(&self, factor: u64) -> Result<Histogram, Error> {
+        let mut result = self.clone();
 
-impl<'a> IntoIterator for &'a Histogram {
-    type Item = Bucket;
-    type IntoIter = Iter<'a>;
+        for (index, bucket) in result.buckets.iter_mut().enumerate() {
+            *bucket = bucket
+                .checked_mul(factor)
+                .ok_or(Error::BucketOverflow { index })?;
+        }
 
-    fn into_iter(self) -> Self::IntoIter {
-        Iter {
+        Ok(result)
+    }
+
+    /// Splits this histogram at a value into two histograms with the same
+    /// config. The first histogram contains the counts of every bucket below
+    /// the bucket which holds the value, and the second histogram contains the
+    /// counts of the bucket which holds the value and every bucket above it.
+    /// All other buckets are zero in each histogram.
+    ///
+    /// Since the split is made on bucket boundaries, values in the same bucket
+    /// as `value` which are less than `value` are assigned to the second
+    /// histogram. The two histograms sum to this histogram.
+    ///
+    /// An error is returned if the value is outside of the storable range.
+    pub fn split_at(&self, value: u64) -> Result<(Histogram, Histogram), Error> {
+        let index = self.config.value_to_index(value)?;
+
+        let mut lower = Histogram::with_config(&self.config);
+        let mut upper = Histogram::with_config(&self.config);
+
+        lower.buckets[..index].copy_from_slice(&self.buckets[..index]);
+        upper.buckets[index..].copy_from_slice(&self.buckets[index..]);
+
+        Ok((lower, upper))
+    }
+
+    /// Retains only the buckets whose range satisfies the predicate. The count
+    /// of every bucket for which `f` returns `false` is set to zero.
+    ///
+    /// This can be used to trim a distribution in place, for example to drop
+    /// outliers above a threshold before exporting. Note that this changes the
+    /// total count of the histogram, so any totals or percentiles computed
+    /// before calling this must be recomputed.
+    pub fn retain(&mut self, f: impl Fn(RangeInclusive<u64>) -> bool) {
+        for (index, count) in self.buckets.iter_mut().enumerate() {
+            if *count > 0 && !f(self.config.index_to_range(index)) {
+                *count = 0;
+            }
+        }
+    }
+
+    /// Computes the distance between the distribution of this histogram and
+    /// the distribution of the other histogram using the provided metric. See
+    /// [`crate::DistanceMetric`] for the supported metrics.
+    ///
+    /// For `KullbackLeibler`, this histogram is treated as the observed
+    /// distribution and the other histogram as the reference distribution.
+    ///
+    /// If both histograms are empty, the distance is zero. If only one is
+    /// empty, the total variation distance is one, and the smoothed
+    /// Kullback-Leibler divergence treats the empty histogram as uniform.
+    ///
+    /// An error is returned if the two histograms have incompatible parameters.
+    pub fn distance(&self, other: &Histogram, metric: DistanceMetric) -> Result<f64, Error> {
+        if self.config != other.config {
+            return Err(Error::IncompatibleParameters);
+        }
+
+        let this_total: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+        let other_total: u128 = other.buckets.iter().map(|v| *v as u128).sum();
+
+        if this_total == 0 && other_total == 0 {
+            return Ok(0.0);
+        }
+
+        let pairs = self.buckets.iter().zip(other.buckets.iter());
+
+        match metric {
+            DistanceMetric::TotalVariation => {
+                if this_total == 0 || other_total == 0 {
+                    return Ok(1.0);
+                }
+
+                let this_total = this_total as f64;
+                let other_total = other_total as f64;
+
+                let sum: f64 = pairs
+                    .map(|(this, other)| {
+                        (*this as f64 / this_total - *other as f64 / other_total).abs()
+                    })
+                    .sum();
+
+                Ok(sum / 2.0)
+            }
+            DistanceMetric::KullbackLeibler => {
+                let buckets = self.buckets.len() as f64;
+                let this_total = this_total as f64 + buckets;
+                let other_total = other_total as f64 + buckets;
+
+                let sum: f64 = pairs
+                    .map(|(this, other)| {
+                        let p = (*this as f64 + 1.0) / this_total;
+                        let q = (*other as f64 + 1.0) / other_total;
+                        p * (p / q).ln()
+                    })
+                    .sum();
+
+                // guard against slightly negative results from rounding
+                Ok(sum.max(0.0))
+            }
+        }
+    }
+
+    /// Returns an iterator of representative `(value, count)` pairs for the
+    /// non-empty buckets, in order of increasing value. This can be used to
+    /// feed the contents of the histogram into tools which expect samples.
+    ///
+    /// Each value is the midpoint of its bucket and each count is the bucket
+    /// count, capped at `max_per_bucket` to bound the number of samples which
+    /// a consumer may materialize.
+    ///
+    /// This is a lossy reconstruction. The original values within each bucket
+    /// are not known, and capping the counts changes the relative weight of
+    /// the buckets.
+    pub fn representative_samples(
+        &self,
+        max_per_bucket: u64,
+    ) -> impl Iterator<Item = (u64, u64)> + '_ {
+        self.into_iter()
+            .filter(|bucket| bucket.count() > 0)
+            .map(move |bucket| (midpoint(&bucket.range), bucket.count().min(max_per_bucket)))
+    }
+
+    /// Returns the cumulative counts of the histogram in the form used by
+    /// OpenMetrics and Prometheus histograms. Each entry is the inclusive upper
+    /// bound of a non-empty bucket and the total count of all observations less
+    /// than or equal to that bound.
+    ///
+    /// The final entry always has an upper bound of `None`, representing the
+    /// `+Inf` bucket, and the total count. It is present even if the highest
+    /// non-empty bucket has an upper bound of `u64::MAX`. The cumulative counts
+    /// saturate at `u64::MAX`.
+    pub fn cumulative_buckets(&self) -> Vec<(Option<u64>, u64)> {
+        let mut cumulative = 0_u64;
+
+        let mut buckets: Vec<(Option<u64>, u64)> = self
+            .into_iter()
+            .filter(|bucket| bucket.count() > 0)
+            .map(|bucket| {
+                cumulative = cumulative.saturating_add(bucket.count());
+                (Some(bucket.end()), cumulative)
+            })
+            .collect();
+
+        buckets.push((None, cumulative));
+
+        buckets
+    }
+
+    /// Returns a probability density estimate of the histogram. For each
+    /// non-empty bucket, this is the range of the bucket and the density
+    /// `count / (total_count * bucket_width)`, where the width is the number
+    /// of values within the bucket's range.
+    ///
+    /// Unlike the raw counts, the density accounts for the buckets in the
+    /// logarithmic range being wider, so the densities multiplied by the bucket
+    /// widths sum to one. An empty histogram has an empty density.
+    pub fn density(&self) -> Vec<(RangeInclusive<u64>, f64)> {
+        let total_count: u128 = self.buckets.iter().map(|v| *v as u128).sum();
+
+        if total_count == 0 {
+            return Vec::new();
+        }
+
+        self.into_iter()
+            .filter(|bucket| bucket.count() > 0)
+            .map(|bucket| {
+                let width = (bucket.end() - bucket.start()) as f64 + 1.0;
+                let density = bucket.count() as f64 / (total_count as f64 * width);
+                (bucket.range(), density)
+            })
+            .collect()
+    }
+
+    /// Returns the bucket configuration of the histogram.
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    /// Returns the number of bytes of heap memory used to store the buckets.
+    pub fn heap_size(&self) -> usize {
+        self.buckets.len() * core::mem::size_of::<u64>()
+    }
+
+    /// Returns the number of buckets which have a non-zero count.
+    ///
+    /// Together with [`Config::total_buckets`], this gives the sparsity of the
+    /// histogram, which can be used to choose between this representation and
+    /// a [`crate::SparseHistogram`].
+    pub fn nonzero_bucket_count(&self) -> usize {
+        self.buckets.iter().filter(|count| **count > 0).count()
+    }
+}
+
+impl<'a> IntoIterator for &'a Histogram {
+    type Item = Bucket;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Iter {
             index: 0,
             histogram: self,
         }
@@ -289,6 +1091,55 @@ impl<'a> Iterator for Iter<'a> {
     }
 }
 
+impl IntoIterator for Histogram {
+    type Item = Bucket;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            index: 0,
+            histogram: self,
+        }
+    }
+}
+
+/// An iterator across the histogram buckets which takes ownership of the
+/// histogram.
+///
+/// ```
+/// use histogram::{Bucket, Histogram};
+///
+/// let mut histogram = Histogram::new(2, 4).unwrap();
+/// histogram.increment(1).unwrap();
+///
+/// let buckets: Vec<Bucket> = histogram.into_iter().collect();
+/// assert_eq!(buckets.len(), 12);
+/// assert_eq!(buckets[1], Bucket::new(1, 1..=1));
+/// ```
+pub struct IntoIter {
+    index: usize,
+    histogram: Histogram,
+}
+
+impl Iterator for IntoIter {
+    type Item = Bucket;
+
+    fn next(&mut self) -> Option<<Self as std::iter::Iterator>::Item> {
+        if self.index >= self.histogram.buckets.len() {
+            return None;
+        }
+
+        let bucket = Bucket {
+            count: self.histogram.buckets[self.index],
+            range: self.histogram.config.index_to_range(self.index),
+        };
+
+        self.index += 1;
+
+        Some(bucket)
+    }
+}
+
 impl From<&SparseHistogram> for Histogram {
     fn from(other: &SparseHistogram) -> Self {
         let mut histogram = Histogram::with_config(&other.config);
@@ -301,6 +1152,17 @@ impl From<&SparseHistogram> for Histogram {
     }
 }
 
+/// Approximates the upper quantile `z` of the standard normal distribution for
+/// an upper tail probability `p` in the range `0.0..=0.5`. This uses formula
+/// 26.2.23 from Abramowitz and Stegun, which has an absolute error below
+/// `4.5e-4`.
+fn normal_quantile(p: f64) -> f64 {
+    let t = (-2.0 * p.ln()).sqrt();
+
+    t - (2.515517 + 0.802853 * t + 0.010328 * t * t)
+        / (1.0 + 1.432788 * t + 0.189269 * t * t + 0.001308 * t * t * t)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -312,6 +1174,405 @@ mod tests {
         assert_eq!(std::mem::size_of::<Histogram>(), 48);
     }
 
+    #[test]
+    fn for_range() {
+        for (max_value, relative_error, grouping_power, max_value_power) in [
+            (1_000_000_000, 0.01, 7, 30),
+            (1000, 0.25, 2, 10),
+            (1024, 0.5, 1, 11),
+            (u64::MAX, 0.001, 10, 64),
+        ] {
+            let histogram = Histogram::for_range(max_value, relative_error).unwrap();
+            assert_eq!(histogram.config().grouping_power(), grouping_power);
+            assert_eq!(histogram.config().max_value_power(), max_value_power);
+            assert!(histogram.config().error() <= relative_error * 100.0);
+        }
+
+        for relative_error in [0.0, -0.1, 1.5, f64::NAN] {
+            assert_eq!(
+                Histogram::for_range(1000, relative_error),
+                Err(Error::InvalidRelativeError)
+            );
+        }
+
+        assert_eq!(Histogram::for_range(3, 0.01), Err(Error::MaxPowerTooLow));
+    }
+
+    #[test]
+    fn heap_size() {
+        let histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.heap_size(), 7424 * 8);
+    }
+
+    #[test]
+    fn nonzero_bucket_count() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.nonzero_bucket_count(), 0);
+
+        let _ = histogram.increment(1);
+        let _ = histogram.increment(1);
+        let _ = histogram.increment(1000);
+        assert_eq!(histogram.nonzero_bucket_count(), 2);
+        assert_eq!(
+            SparseHistogram::from(&histogram).index.len(),
+            histogram.nonzero_bucket_count()
+        );
+    }
+
+    #[test]
+    fn increment_unchecked() {
+        let mut checked = Histogram::new(7, 32).unwrap();
+        let mut unchecked = Histogram::new(7, 32).unwrap();
+
+        for value in [0, 1, 255, 256, 1024, 1_000_000, u32::MAX as u64] {
+            checked.increment(value).unwrap();
+            unchecked.increment_unchecked(value);
+        }
+
+        assert_eq!(checked, unchecked);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn increment_unchecked_out_of_range() {
+        let mut histogram = Histogram::new(7, 32).unwrap();
+        histogram.increment_unchecked(u64::MAX);
+    }
+
+    #[test]
+    fn percentile_ci() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.percentile_ci(50.0, 0.95), Ok(None));
+        assert_eq!(
+            histogram.percentile_ci(101.0, 0.95),
+            Err(Error::InvalidPercentile)
+        );
+        assert_eq!(
+            histogram.percentile_ci(50.0, 1.0),
+            Err(Error::InvalidConfidence)
+        );
+        assert_eq!(
+            histogram.percentile_ci(50.0, 0.0),
+            Err(Error::InvalidConfidence)
+        );
+
+        // a small sample gives a wide interval
+        for value in 1..=10 {
+            let _ = histogram.increment(value);
+        }
+        let (lower, upper) = histogram.percentile_ci(50.0, 0.95).unwrap().unwrap();
+        assert!(lower <= 3 && upper >= 8);
+
+        // a large sample gives a narrow interval around the percentile
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        for value in 1..=100_000 {
+            let _ = histogram.increment(value);
+        }
+        let (lower, upper) = histogram.percentile_ci(50.0, 0.95).unwrap().unwrap();
+        let median = histogram.percentile(50.0).unwrap().unwrap();
+        assert!(lower <= median.start() && upper >= median.end());
+        assert!(lower >= 49_500 && upper <= 50_500);
+
+        // the interval widens with the confidence
+        let (wide_lower, wide_upper) = histogram.percentile_ci(50.0, 0.999).unwrap().unwrap();
+        assert!(wide_lower <= lower && wide_upper >= upper);
+
+        // the extremes are clamped to the observed ranks
+        let (lower, upper) = histogram.percentile_ci(100.0, 0.95).unwrap().unwrap();
+        assert_eq!(upper, histogram.percentile(100.0).unwrap().unwrap().end());
+        assert!(lower <= upper);
+    }
+
+    #[test]
+    fn add_saturating_count() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+
+        histogram.add_saturating_count(1, u64::MAX - 1).unwrap();
+        histogram.add_saturating_count(1, u64::MAX - 1).unwrap();
+        assert_eq!(histogram.as_slice()[1], u64::MAX);
+
+        // the default add wraps instead
+        histogram.add(2, u64::MAX - 1).unwrap();
+        histogram.add(2, u64::MAX - 1).unwrap();
+        assert_eq!(histogram.as_slice()[2], u64::MAX - 3);
+
+        assert_eq!(
+            Histogram::new(7, 32)
+                .unwrap()
+                .add_saturating_count(u64::MAX, 1),
+            Err(Error::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn central_interval() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.central_interval(0.8), Ok(None));
+        assert_eq!(histogram.central_interval(0.0), Err(Error::InvalidFraction));
+        assert_eq!(histogram.central_interval(1.1), Err(Error::InvalidFraction));
+
+        for value in 1..=100 {
+            let _ = histogram.increment(value);
+        }
+
+        // the middle 80% is from p10 to p90
+        assert_eq!(histogram.central_interval(0.8), Ok(Some(10..=90)));
+        assert_eq!(histogram.central_interval(1.0), Ok(Some(1..=100)));
+    }
+
+    #[test]
+    fn effective_resolution() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.effective_resolution(), f64::INFINITY);
+
+        let _ = histogram.add(1, 50);
+        assert_eq!(histogram.effective_resolution(), 2.0);
+
+        let _ = histogram.add(1000, 999_950);
+        assert_eq!(histogram.effective_resolution(), 0.0001);
+    }
+
+    #[test]
+    fn new_prefaulted() {
+        let histogram = Histogram::new_prefaulted(14, 64).unwrap();
+        assert_eq!(histogram, Histogram::new(14, 64).unwrap());
+
+        assert_eq!(
+            Histogram::new_prefaulted(7, 65),
+            Err(Error::MaxPowerTooHigh)
+        );
+    }
+
+    #[test]
+    fn percentile_or_err() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.percentile_or_err(50.0), Err(Error::Empty));
+        assert_eq!(histogram.percentiles_or_err(&[50.0]), Err(Error::Empty));
+        assert_eq!(
+            histogram.percentile_or_err(101.0),
+            Err(Error::InvalidPercentile)
+        );
+
+        let _ = histogram.increment(1);
+        assert_eq!(
+            histogram.percentile_or_err(50.0),
+            Ok(histogram.percentile(50.0).unwrap().unwrap())
+        );
+        assert_eq!(
+            histogram.percentiles_or_err(&[50.0, 99.0]),
+            Ok(histogram.percentiles(&[50.0, 99.0]).unwrap().unwrap())
+        );
+    }
+
+    #[test]
+    fn checked_scale() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        for value in 1..=100 {
+            let _ = histogram.add(value, value);
+        }
+
+        let scaled = histogram.checked_scale(10).unwrap();
+        assert_eq!(
+            scaled.as_slice().iter().sum::<u64>(),
+            10 * histogram.as_slice().iter().sum::<u64>()
+        );
+
+        let percentiles = [1.0, 25.0, 50.0, 99.0];
+        let ranges = |h: &Histogram| -> Vec<_> {
+            h.percentiles(&percentiles)
+                .unwrap()
+                .unwrap()
+                .into_iter()
+                .map(|(_, bucket)| bucket.range())
+                .collect()
+        };
+        assert_eq!(ranges(&scaled), ranges(&histogram));
+
+        let _ = histogram.add(1000, u64::MAX / 2);
+        let index = histogram.percentile_index(100.0).unwrap().unwrap();
+        assert_eq!(
+            histogram.checked_scale(3),
+            Err(Error::BucketOverflow { index })
+        );
+    }
+
+    #[test]
+    fn cumulative_buckets() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.cumulative_buckets(), vec![(None, 0)]);
+
+        let _ = histogram.add(1, 2);
+        let _ = histogram.add(10, 3);
+        let _ = histogram.add(1000, 5);
+
+        let buckets = histogram.cumulative_buckets();
+        assert_eq!(
+            buckets,
+            vec![(Some(1), 2), (Some(10), 5), (Some(1003), 10), (None, 10)]
+        );
+        for pair in buckets[..buckets.len() - 1].windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+            assert!(pair[0].1 <= pair[1].1);
+        }
+
+        // a bucket ending at u64::MAX is distinct from the +Inf bucket
+        let _ = histogram.increment(u64::MAX);
+        let buckets = histogram.cumulative_buckets();
+        assert_eq!(buckets.len(), 5);
+        assert_eq!(buckets[3], (Some(u64::MAX), 11));
+        assert_eq!(buckets[4], (None, 11));
+    }
+
+    #[test]
+    fn summary() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.summary(&[50.0]), Ok(None));
+        assert_eq!(histogram.summary(&[101.0]), Err(Error::InvalidPercentile));
+
+        for value in 1..=1000 {
+            let _ = histogram.increment(value);
+        }
+
+        let summary = histogram.summary(&[99.0, 50.0]).unwrap().unwrap();
+        assert_eq!(summary.count(), 1000);
+        assert_eq!(summary.min(), 1);
+        assert_eq!(summary.max(), 1003);
+        assert!((summary.mean() - 500.5).abs() < 1.0);
+        assert_eq!(
+            summary.percentiles(),
+            histogram.percentiles(&[50.0, 99.0]).unwrap().unwrap()
+        );
+
+        // duplicate and extreme percentiles match a separate lookup
+        let summary = histogram
+            .summary(&[100.0, 0.0, 50.0, 50.0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            summary.percentiles(),
+            histogram
+                .percentiles(&[0.0, 50.0, 50.0, 100.0])
+                .unwrap()
+                .unwrap()
+        );
+
+        // the min and max match the extreme percentiles
+        assert_eq!(
+            summary.min(),
+            histogram.percentile(0.0).unwrap().unwrap().start()
+        );
+        assert_eq!(
+            summary.max(),
+            histogram.percentile(100.0).unwrap().unwrap().end()
+        );
+    }
+
+    #[test]
+    fn percentile_with_mass() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.percentile_with_mass(50.0), Ok(None));
+        assert_eq!(
+            histogram.percentile_with_mass(-1.0),
+            Err(Error::InvalidPercentile)
+        );
+
+        let _ = histogram.add(1, 97);
+        let _ = histogram.add(200, 3);
+
+        assert_eq!(
+            histogram.percentile_with_mass(50.0),
+            Ok(Some((
+                Bucket {
+                    count: 97,
+                    range: 1..=1,
+                },
+                0.97
+            )))
+        );
+        assert_eq!(
+            histogram.percentile_with_mass(99.0),
+            Ok(Some((
+                Bucket {
+                    count: 3,
+                    range: 200..=200,
+                },
+                0.03
+            )))
+        );
+    }
+
+    #[test]
+    // Tests that the percentile index matches the percentile bucket
+    fn percentile_index() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.percentile_index(50.0), Ok(None));
+        assert_eq!(
+            histogram.percentile_index(100.1),
+            Err(Error::InvalidPercentile)
+        );
+
+        for value in 1..=10_000 {
+            let _ = histogram.increment(value);
+        }
+
+        for percentile in [0.0, 1.0, 25.0, 50.0, 90.0, 99.0, 99.9, 100.0] {
+            let index = histogram.percentile_index(percentile).unwrap().unwrap();
+            let bucket = histogram.percentile(percentile).unwrap().unwrap();
+            assert_eq!(histogram.config.index_to_range(index), bucket.range());
+        }
+    }
+
+    #[test]
+    // Tests percentiles with integer basis points
+    fn percentile_bp() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.percentile_bp(500_000), Ok(None));
+        assert_eq!(
+            histogram.percentile_bp(1_000_001),
+            Err(Error::InvalidPercentile)
+        );
+
+        for value in 1..=1_000_000 {
+            let _ = histogram.increment(value);
+        }
+
+        for (bp, percentile) in [
+            (999_999, 99.9999),
+            (999_990, 99.999),
+            (999_900, 99.99),
+            (500_000, 50.0),
+            (1_000_000, 100.0),
+            (0, 0.0),
+        ] {
+            assert_eq!(
+                histogram.percentile_bp(bp),
+                histogram.percentile(percentile)
+            );
+        }
+
+        // 99.9% of 1000 is exactly rank 999, but the floating point rank
+        // rounds up to 1000
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        let _ = histogram.add(1, 999);
+        let _ = histogram.increment(1000);
+
+        assert_eq!(
+            histogram.percentile_bp(999_000),
+            Ok(Some(Bucket {
+                count: 999,
+                range: 1..=1,
+            }))
+        );
+        assert_eq!(
+            histogram.percentile(99.9),
+            Ok(Some(Bucket {
+                count: 1,
+                range: 1000..=1003,
+            }))
+        );
+    }
+
     #[test]
     // Tests percentiles
     fn percentiles() {
@@ -376,6 +1637,113 @@ mod tests {
         );
     }
 
+    #[test]
+    // Tests percentiles with each of the rounding rules
+    fn percentiles_with() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+
+        assert_eq!(
+            histogram.percentiles_with(&[50.0], Rounding::Floor),
+            Ok(None)
+        );
+
+        for i in 1..=4 {
+            let _ = histogram.increment(i);
+        }
+
+        let end = |p: f64, rounding: Rounding| {
+            histogram
+                .percentiles_with(&[p], rounding)
+                .map(|v| v.unwrap().first().unwrap().1.end())
+        };
+
+        // unrounded rank of 2.5
+        assert_eq!(end(62.5, Rounding::Floor), Ok(2));
+        assert_eq!(end(62.5, Rounding::Ceil), Ok(3));
+        assert_eq!(end(62.5, Rounding::Round), Ok(3));
+        assert_eq!(end(62.5, Rounding::Nearest), Ok(2));
+
+        // unrounded rank of 3.5
+        assert_eq!(end(87.5, Rounding::Round), Ok(4));
+        assert_eq!(end(87.5, Rounding::Nearest), Ok(4));
+
+        assert_eq!(
+            histogram.percentiles(&[25.0, 50.0, 99.0]),
+            histogram.percentiles_with(&[25.0, 50.0, 99.0], Rounding::Ceil)
+        );
+
+        assert_eq!(
+            histogram.percentiles_with(&[101.0], Rounding::Floor),
+            Err(Error::InvalidPercentile)
+        );
+    }
+
+    #[test]
+    // Tests that percentiles can ignore observations of zero
+    fn percentiles_excluding_zero() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+
+        let _ = histogram.add(0, 1000);
+        assert_eq!(histogram.percentiles_excluding_zero(&[50.0]), Ok(None));
+
+        for i in 1..=100 {
+            let _ = histogram.increment(i);
+        }
+
+        // the zeros dominate the regular percentiles
+        assert_eq!(histogram.percentile(50.0).map(|b| b.unwrap().end()), Ok(0));
+
+        let percentiles: Vec<(f64, u64)> = histogram
+            .percentiles_excluding_zero(&[1.0, 50.0, 90.0, 100.0])
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(|(p, b)| (*p, b.end()))
+            .collect();
+
+        assert_eq!(
+            percentiles,
+            vec![(1.0, 1), (50.0, 50), (90.0, 90), (100.0, 100)]
+        );
+
+        assert_eq!(
+            histogram.percentiles_excluding_zero(&[101.0]),
+            Err(Error::InvalidPercentile)
+        );
+    }
+
+    #[test]
+    // Tests fixed-size percentiles match the allocating variant
+    fn percentiles_array() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+
+        assert_eq!(histogram.percentiles_array([50.0, 99.0]), Ok(None));
+
+        for i in 0..=1000 {
+            let _ = histogram.increment(i);
+        }
+
+        let expected: Vec<Bucket> = histogram
+            .percentiles(&[25.0, 50.0, 99.9])
+            .unwrap()
+            .unwrap()
+            .into_iter()
+            .map(|(_, b)| b)
+            .collect();
+
+        // results are in the requested order, not sorted
+        let [p99_9, p25, p50] = histogram
+            .percentiles_array([99.9, 25.0, 50.0])
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![p25, p50, p99_9], expected);
+
+        assert_eq!(
+            histogram.percentiles_array([50.0, 101.0]),
+            Err(Error::InvalidPercentile)
+        );
+    }
+
     #[test]
     #[ignore = "this test is flaky (see issue #100)"]
     // Tests downsampling
@@ -452,7 +1820,16 @@ mod tests {
         let r = h.checked_add(&h_good).unwrap();
         assert_eq!(r.as_slice(), &[2, 2, 2, 2, 2, 2]);
 
-        assert_eq!(h.checked_add(&h_overflow), Err(Error::Overflow));
+        assert_eq!(
+            h.checked_add(&h_overflow),
+            Err(Error::BucketOverflow { index: 0 })
+        );
+
+        let partial = Histogram::from_buckets(1, 3, vec![0, 0, 0, u64::MAX, 0, 0]).unwrap();
+        assert_eq!(
+            h.checked_add(&partial),
+            Err(Error::BucketOverflow { index: 3 })
+        );
     }
 
     #[test]
@@ -472,6 +1849,187 @@ mod tests {
         assert_eq!(r.as_slice(), &[0, 0, 0, 0, 0, 0]);
     }
 
+    #[test]
+    // Tests adding histograms with different max value powers
+    fn add_extended() {
+        let mut small = Histogram::new(7, 30).unwrap();
+        let mut large = Histogram::new(7, 32).unwrap();
+
+        let _ = small.increment(1);
+        let _ = small.increment(1_000_000);
+        let _ = large.increment(1_000_000);
+        let _ = large.increment(3_000_000_000);
+
+        let mut expected = Histogram::new(7, 32).unwrap();
+        let _ = expected.increment(1);
+        let _ = expected.add(1_000_000, 2);
+        let _ = expected.increment(3_000_000_000);
+
+        assert_eq!(large.add_extended(&small), Ok(expected.clone()));
+        assert_eq!(small.add_extended(&large), Ok(expected));
+
+        let mismatch = Histogram::new(6, 32).unwrap();
+        assert_eq!(
+            small.add_extended(&mismatch),
+            Err(Error::IncompatibleParameters)
+        );
+
+        let mut full = Histogram::new(7, 30).unwrap();
+        let _ = full.add(1, u64::MAX);
+        assert!(large.add_extended(&full).is_ok());
+        assert_eq!(
+            small.add_extended(&full),
+            Err(Error::BucketOverflow { index: 1 })
+        );
+    }
+
+    #[test]
+    // Tests in-place add matches the cloning add variants
+    fn add_assign() {
+        let (h, h_good, h_overflow, h_mismatch) = build_histograms();
+
+        let mut r = h.clone();
+        assert_eq!(
+            r.add_assign_checked(&h_mismatch),
+            Err(Error::IncompatibleParameters)
+        );
+        assert_eq!(
+            r.add_assign_wrapping(&h_mismatch),
+            Err(Error::IncompatibleParameters)
+        );
+
+        let mut r = h.clone();
+        r.add_assign_checked(&h_good).unwrap();
+        assert_eq!(r, h.checked_add(&h_good).unwrap());
+
+        let mut r = h.clone();
+        assert_eq!(
+            r.add_assign_checked(&h_overflow),
+            Err(Error::BucketOverflow { index: 0 })
+        );
+        assert_eq!(r, h);
+
+        let mut r = h.clone();
+        r.add_assign_wrapping(&h_good).unwrap();
+        assert_eq!(r, h.wrapping_add(&h_good).unwrap());
+
+        let mut r = h.clone();
+        r.add_assign_wrapping(&h_overflow).unwrap();
+        assert_eq!(r, h.wrapping_add(&h_overflow).unwrap());
+    }
+
+    #[test]
+    fn representative_samples() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert_eq!(histogram.representative_samples(10).count(), 0);
+
+        let _ = histogram.add(1, 5);
+        let _ = histogram.add(1000, 50);
+
+        let samples: Vec<(u64, u64)> = histogram.representative_samples(10).collect();
+        assert_eq!(samples, vec![(1, 5), (1001, 10)]);
+
+        let samples: Vec<(u64, u64)> = histogram.representative_samples(u64::MAX).collect();
+        assert_eq!(samples, vec![(1, 5), (1001, 50)]);
+    }
+
+    #[test]
+    fn density() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        assert!(histogram.density().is_empty());
+
+        let _ = histogram.add(1, 4);
+        let _ = histogram.add(1000, 4);
+        let _ = histogram.increment(u64::MAX);
+
+        // narrow buckets have a higher density than wide buckets with the same
+        // count
+        let density = histogram.density();
+        assert_eq!(density.len(), 3);
+        assert_eq!(density[0], (1..=1, 4.0 / 9.0));
+        assert_eq!(density[1], (1000..=1003, 1.0 / 9.0));
+
+        for value in 0..1000 {
+            let _ = histogram.add(value * 1000, value);
+        }
+
+        let density = histogram.density();
+        assert_eq!(density.len(), histogram.nonzero_bucket_count());
+
+        let sum: f64 = density
+            .iter()
+            .map(|(range, density)| density * ((range.end() - range.start()) as f64 + 1.0))
+            .sum();
+        assert!((sum - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn split_at() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        for value in 0..=2000 {
+            let _ = histogram.increment(value);
+        }
+
+        let (lower, upper) = histogram.split_at(100).unwrap();
+        assert_eq!(lower.config(), histogram.config());
+        assert_eq!(upper.config(), histogram.config());
+        assert_eq!(lower.as_slice().iter().sum::<u64>(), 100);
+        assert_eq!(upper.as_slice().iter().sum::<u64>(), 1901);
+        assert_eq!(lower.checked_add(&upper).unwrap(), histogram);
+
+        // the bucket holding the value is assigned to the upper histogram
+        let (lower, upper) = histogram.split_at(1001).unwrap();
+        assert_eq!(lower.percentile(100.0).unwrap().unwrap().range(), 996..=999);
+        assert_eq!(upper.percentile(0.0).unwrap().unwrap().range(), 1000..=1003);
+        assert_eq!(lower.checked_add(&upper).unwrap(), histogram);
+
+        let (lower, upper) = histogram.split_at(0).unwrap();
+        assert_eq!(lower.percentile(50.0), Ok(None));
+        assert_eq!(upper, histogram);
+
+        let histogram = Histogram::new(7, 32).unwrap();
+        assert_eq!(histogram.split_at(u64::MAX), Err(Error::OutOfRange));
+    }
+
+    #[test]
+    fn retain() {
+        let mut histogram = Histogram::new(7, 64).unwrap();
+        for value in 0..=2000 {
+            let _ = histogram.increment(value);
+        }
+
+        histogram.retain(|range| *range.end() < 1000);
+        assert_eq!(histogram.as_slice().iter().sum::<u64>(), 1000);
+        assert_eq!(histogram.percentile(0.0).unwrap().unwrap().range(), 0..=0);
+        assert_eq!(
+            histogram.percentile(100.0).unwrap().unwrap().range(),
+            996..=999
+        );
+
+        histogram.retain(|_| false);
+        assert_eq!(histogram.percentile(50.0), Ok(None));
+    }
+
+    #[test]
+    // Tests adding raw counts matches wrapping add
+    fn add_slice_counts() {
+        let (h, h_good, h_overflow, h_mismatch) = build_histograms();
+
+        let mut r = h.clone();
+        assert_eq!(
+            r.add_slice_counts(h_mismatch.as_slice()),
+            Err(Error::IncompatibleParameters)
+        );
+        assert_eq!(r, h);
+
+        r.add_slice_counts(h_good.as_slice()).unwrap();
+        assert_eq!(r, h.wrapping_add(&h_good).unwrap());
+
+        let mut r = h.clone();
+        r.add_slice_counts(h_overflow.as_slice()).unwrap();
+        assert_eq!(r, h.wrapping_add(&h_overflow).unwrap());
+    }
+
     #[test]
     // Tests checked sub
     fn checked_sub() {
@@ -485,7 +2043,16 @@ mod tests {
         let r = h.checked_sub(&h_good).unwrap();
         assert_eq!(r.as_slice(), &[0, 0, 0, 0, 0, 0]);
 
-        assert_eq!(h.checked_add(&h_overflow), Err(Error::Overflow));
+        assert_eq!(
+            h.checked_sub(&h_overflow),
+            Err(Error::BucketUnderflow { index: 0 })
+        );
+
+        let partial = Histogram::from_buckets(1, 3, vec![0, 0, 2, 0, 0, 0]).unwrap();
+        assert_eq!(
+            h.checked_sub(&partial),
+            Err(Error::BucketUnderflow { index: 2 })
+        );
     }
 
     #[test]
@@ -505,6 +2072,59 @@ mod tests {
         assert_eq!(r.as_slice(), &[2, 2, 2, 2, 2, 2]);
     }
 
+    #[test]
+    // Tests distance between histograms
+    fn distance() {
+        let (h, h_good, _, h_mismatch) = build_histograms();
+
+        for metric in [
+            DistanceMetric::TotalVariation,
+            DistanceMetric::KullbackLeibler,
+        ] {
+            assert_eq!(
+                h.distance(&h_mismatch, metric),
+                Err(Error::IncompatibleParameters)
+            );
+
+            // identical distributions have no distance
+            assert_eq!(h.distance(&h_good, metric), Ok(0.0));
+
+            // as do two empty histograms
+            let empty = Histogram::new(1, 3).unwrap();
+            assert_eq!(empty.distance(&empty, metric), Ok(0.0));
+        }
+
+        let empty = Histogram::new(1, 3).unwrap();
+        let low = Histogram::from_buckets(1, 3, vec![4, 4, 0, 0, 0, 0]).unwrap();
+        let high = Histogram::from_buckets(1, 3, vec![0, 0, 0, 0, 1, 1]).unwrap();
+        let mixed = Histogram::from_buckets(1, 3, vec![1, 1, 0, 0, 1, 1]).unwrap();
+
+        // disjoint distributions have the maximum total variation distance
+        assert_eq!(low.distance(&high, DistanceMetric::TotalVariation), Ok(1.0));
+        assert_eq!(
+            low.distance(&mixed, DistanceMetric::TotalVariation),
+            Ok(0.5)
+        );
+        assert_eq!(
+            low.distance(&empty, DistanceMetric::TotalVariation),
+            Ok(1.0)
+        );
+
+        // smoothing keeps the divergence finite despite empty buckets
+        let kl = low
+            .distance(&high, DistanceMetric::KullbackLeibler)
+            .unwrap();
+        assert!(kl.is_finite() && kl > 0.0);
+        let kl_mixed = low
+            .distance(&mixed, DistanceMetric::KullbackLeibler)
+            .unwrap();
+        assert!(kl_mixed > 0.0 && kl_mixed < kl);
+        let kl_empty = low
+            .distance(&empty, DistanceMetric::KullbackLeibler)
+            .unwrap();
+        assert!(kl_empty.is_finite() && kl_empty > 0.0);
+    }
+
     #[test]
     // Test creating the histogram from buckets
     fn from_buckets() {
@@ -0,0 +1,44 @@
+use crate::Bucket;
+
+/// A summary of the distribution of a [`crate::Histogram`], as returned by
+/// [`crate::Histogram::summary`].
+///
+/// The min and max are the bounds of the lowest and highest non-empty buckets,
+/// so they are subject to the same error as the bucket ranges. The mean is
+/// computed using the midpoint of each bucket.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub(crate) count: u128,
+    pub(crate) min: u64,
+    pub(crate) max: u64,
+    pub(crate) mean: f64,
+    pub(crate) percentiles: Vec<(f64, Bucket)>,
+}
+
+impl Summary {
+    /// Returns the total number of observations.
+    pub fn count(&self) -> u128 {
+        self.count
+    }
+
+    /// Returns the lower bound of the lowest non-empty bucket.
+    pub fn min(&self) -> u64 {
+        self.min
+    }
+
+    /// Returns the upper bound of the highest non-empty bucket.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// Returns the mean of the observations, using the midpoint of each bucket.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the requested percentiles, sorted by the percentile, with the
+    /// bucket for each percentile.
+    pub fn percentiles(&self) -> &[(f64, Bucket)] {
+        &self.percentiles
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{Error, Histogram};
+use core::ops::Deref;
+
+/// A [`crate::Histogram`] with its grouping power `G` and max value power `N`
+/// fixed at the type level.
+///
+/// The dynamic API represents the parameters as a runtime [`crate::Config`],
+/// so combining two histograms must check that their configs match and
+/// returns [`crate::Error::IncompatibleParameters`] if they do not. When the
+/// parameters are known statically, this wrapper moves that check to compile
+/// time. Operations which combine histograms only accept a `TypedHistogram`
+/// with the same parameters, and invalid parameters fail to compile.
+///
+/// A `TypedHistogram` dereferences to the underlying `Histogram`, so the rest
+/// of the read-only API, such as percentiles, can be used directly.
+///
+/// ```
+/// use histogram::TypedHistogram;
+///
+/// let mut a = TypedHistogram::<7, 64>::new();
+/// let mut b = TypedHistogram::<7, 64>::new();
+/// a.increment(1).unwrap();
+/// b.increment(2).unwrap();
+///
+/// let c = a.wrapping_add(&b);
+/// assert_eq!(c.config().grouping_power(), 7);
+/// ```
+///
+/// Combining histograms with different parameters does not compile:
+///
+/// ```compile_fail
+/// use histogram::TypedHistogram;
+///
+/// let a = TypedHistogram::<7, 64>::new();
+/// let b = TypedHistogram::<6, 64>::new();
+/// let _ = a.wrapping_add(&b);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypedHistogram<const G: u8, const N: u8> {
+    histogram: Histogram,
+}
+
+impl<const G: u8, const N: u8> TypedHistogram<G, N> {
+    /// Evaluated at compile time to reject invalid parameters.
+    const VALID: () = assert!(
+        N <= 64 && G < N,
+        "invalid histogram parameters, see Config for the constraints"
+    );
+
+    /// Construct a new histogram.
+    pub fn new() -> Self {
+        // referencing the constant forces the compile time check
+        #[allow(clippy::let_unit_value)]
+        let _ = Self::VALID;
+
+        Self {
+            histogram: Histogram::new(G, N).expect("parameters are checked at compile time"),
+        }
+    }
+
+    /// Returns the underlying histogram.
+    pub fn into_inner(self) -> Histogram {
+        self.histogram
+    }
+
+    /// Increment the counter for the bucket corresponding to the provided value
+    /// by one.
+    pub fn increment(&mut self, value: u64) -> Result<(), Error> {
+        self.histogram.increment(value)
+    }
+
+    /// Add some count to the counter for the bucket corresponding to the
+    /// provided value.
+    pub fn add(&mut self, value: u64, count: u64) -> Result<(), Error> {
+        self.histogram.add(value, count)
+    }
+
+    /// Adds the other histogram to this histogram and returns the result as a
+    /// new histogram.
+    ///
+    /// An error is returned if there is an overflow.
+    pub fn checked_add(&self, other: &Self) -> Result<Self, Error> {
+        self.combine(other, |index, a, b| {
+            a.checked_add(b).ok_or(Error::BucketOverflow { index })
+        })
+    }
+
+    /// Adds the other histogram to this histogram and returns the result as a
+    /// new histogram. Buckets are allowed to wrap.
+    pub fn wrapping_add(&self, other: &Self) -> Self {
+        self.combine(other, |_, a, b| Ok(a.wrapping_add(b)))
+            .expect("wrapping operations cannot fail")
+    }
+
+    /// Subtracts the other histogram from this histogram and returns the result
+    /// as a new histogram.
+    ///
+    /// An error is returned if there is an underflow.
+    pub fn checked_sub(&self, other: &Self) -> Result<Self, Error> {
+        self.combine(other, |index, a, b| {
+            a.checked_sub(b).ok_or(Error::BucketUnderflow { index })
+        })
+    }
+
+    /// Subtracts the other histogram from this histogram and returns the result
+    /// as a new histogram. Buckets are allowed to wrap.
+    pub fn wrapping_sub(&self, other: &Self) -> Self {
+        self.combine(other, |_, a, b| Ok(a.wrapping_sub(b)))
+            .expect("wrapping operations cannot fail")
+    }
+
+    /// Internal function to combine the buckets of two histograms, which are
+    /// known to have the same config.
+    fn combine(
+        &self,
+        other: &Self,
+        op: impl Fn(usize, u64, u64) -> Result<u64, Error>,
+    ) -> Result<Self, Error> {
+        let mut result = self.clone();
+
+        for (index, (this, other)) in result
+            .histogram
+            .buckets
+            .iter_mut()
+            .zip(other.histogram.buckets.iter())
+            .enumerate()
+        {
+            *this = op(index, *this, *other)?;
+        }
+
+        Ok(result)
+    }
+}
+
+impl<const G: u8, const N: u8> Default for TypedHistogram<G, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const G: u8, const N: u8> Deref for TypedHistogram<G, N> {
+    type Target = Histogram;
+
+    fn deref(&self) -> &Histogram {
+        &self.histogram
+    }
+}
+
+impl<const G: u8, const N: u8> TryFrom<Histogram> for TypedHistogram<G, N> {
+    type Error = Error;
+
+    /// Converts a histogram into a typed histogram, returning an error if its
+    /// parameters do not match.
+    fn try_from(histogram: Histogram) -> Result<Self, Error> {
+        if histogram.config.grouping_power() != G || histogram.config.max_value_power() != N {
+            return Err(Error::IncompatibleParameters);
+        }
+
+        Ok(Self { histogram })
+    }
+}
+
+impl<const G: u8, const N: u8> From<TypedHistogram<G, N>> for Histogram {
+    fn from(typed: TypedHistogram<G, N>) -> Self {
+        typed.histogram
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed() {
+        let mut a = TypedHistogram::<1, 3>::new();
+        let mut b = TypedHistogram::<1, 3>::default();
+        a.add(1, 2).unwrap();
+        b.increment(1).unwrap();
+        b.increment(7).unwrap();
+
+        let sum = a.checked_add(&b).unwrap();
+        assert_eq!(sum.as_slice(), &[0, 3, 0, 0, 0, 1]);
+        assert_eq!(sum, a.wrapping_add(&b));
+
+        assert_eq!(sum.checked_sub(&b).unwrap(), a);
+        assert_eq!(a.checked_sub(&b), Err(Error::BucketUnderflow { index: 5 }));
+        assert_eq!(a.wrapping_sub(&b).as_slice()[5], u64::MAX);
+
+        let mut full = TypedHistogram::<1, 3>::new();
+        full.add(7, u64::MAX).unwrap();
+        assert_eq!(
+            b.checked_add(&full),
+            Err(Error::BucketOverflow { index: 5 })
+        );
+
+        let histogram: Histogram = sum.clone().into();
+        assert_eq!(TypedHistogram::<1, 3>::try_from(histogram.clone()), Ok(sum));
+        assert_eq!(
+            TypedHistogram::<2, 3>::try_from(histogram),
+            Err(Error::IncompatibleParameters)
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{Builder, Error, RatelimitMode, Ratelimiter};
+use clocksource::precise::{AtomicInstant, Duration, Instant};
+use core::hash::{BuildHasher, Hash};
+use core::marker::PhantomData;
+use core::sync::atomic::Ordering;
+use parking_lot::RwLock;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+
+/// A single token bucket and the time it was last used.
+struct Entry {
+    ratelimiter: Ratelimiter,
+    last_used: AtomicInstant,
+}
+
+impl Entry {
+    /// Returns true if the entry has not been used within the timeout. Another
+    /// thread may have used the entry after `now` was taken, in which case the
+    /// entry is not idle.
+    fn is_idle(&self, now: Instant, timeout: Duration) -> bool {
+        now.checked_duration_since(self.last_used.load(Ordering::Relaxed))
+            .map(|idle| idle >= timeout)
+            .unwrap_or(false)
+    }
+}
+
+/// The keys assigned to a shard, and the earliest time at which the shard
+/// should next be swept for idle keys.
+struct Shard<K> {
+    entries: HashMap<K, Entry>,
+    next_sweep: Instant,
+}
+
+impl<K> Shard<K> {
+    /// Removes all entries which have not been used within the timeout and
+    /// schedules the next sweep one timeout from now.
+    fn sweep(&mut self, now: Instant, timeout: Duration) {
+        self.entries.retain(|_, entry| !entry.is_idle(now, timeout));
+        self.next_sweep = now + timeout;
+    }
+}
+
+/// A ratelimiter which maintains an independent token bucket for each key.
+///
+/// Every key is limited using the same parameters, but tokens are tracked
+/// separately for each key. Token buckets are created lazily the first time a
+/// key is seen. The keys are spread across a number of shards, each protected
+/// by its own lock, to reduce contention between threads.
+///
+/// To bound memory utilization, an idle timeout may be configured. Keys which
+/// have not been used within the idle timeout are evicted, and will start with
+/// a fresh token bucket if they are seen again.
+///
+/// ```
+/// use ratelimit::KeyedRatelimiter;
+/// use std::time::Duration;
+///
+/// let ratelimiter: KeyedRatelimiter<String> =
+///     KeyedRatelimiter::builder(1, Duration::from_secs(1))
+///         .initial_available(1)
+///         .idle_timeout(Duration::from_secs(60))
+///         .build()
+///         .unwrap();
+///
+/// assert!(ratelimiter.try_wait(&"alice".to_string()).is_ok());
+/// assert!(ratelimiter.try_wait(&"bob".to_string()).is_ok());
+/// assert!(ratelimiter.try_wait(&"alice".to_string()).is_err());
+/// ```
+pub struct KeyedRatelimiter<K> {
+    builder: Builder,
+    hasher: RandomState,
+    idle_timeout: Option<Duration>,
+    shards: Box<[RwLock<Shard<K>>]>,
+}
+
+impl<K: Hash + Eq + Clone> KeyedRatelimiter<K> {
+    /// Initialize a builder that will construct a `KeyedRatelimiter` that adds
+    /// the specified `amount` of tokens to the token bucket for each key after
+    /// each `interval` has elapsed.
+    ///
+    /// See [`crate::Ratelimiter::builder`] for details.
+    pub fn builder(amount: u64, interval: core::time::Duration) -> KeyedBuilder<K> {
+        KeyedBuilder::new(amount, interval)
+    }
+
+    /// Non-blocking function to "wait" for a single token for the provided
+    /// key. On success, a single token has been acquired. On failure, a
+    /// `Duration` hinting at when the next refill for the key would occur is
+    /// returned.
+    ///
+    /// If the key has not been seen before, or was evicted, a new token bucket
+    /// is created for it.
+    pub fn try_wait(&self, key: &K) -> Result<(), core::time::Duration> {
+        let now = Instant::now();
+        let shard = &self.shards[self.shard(key)];
+
+        // fast path, the key already has a token bucket
+        {
+            let shard = shard.read();
+            if let Some(entry) = shard.entries.get(key) {
+                entry.last_used.store(now, Ordering::Relaxed);
+                return entry.ratelimiter.try_wait();
+            }
+        }
+
+        // slow path, we need to create a token bucket for this key
+        let mut shard = shard.write();
+
+        // take the opportunity to evict any idle keys from this shard, at most
+        // once per idle timeout so that inserts don't scan the whole shard
+        if let Some(idle_timeout) = self.idle_timeout {
+            if now >= shard.next_sweep {
+                shard.sweep(now, idle_timeout);
+            }
+        }
+
+        let entry = shard.entries.entry(key.clone()).or_insert_with(|| Entry {
+            ratelimiter: self
+                .builder
+                .clone()
+                .build()
+                .expect("parameters were validated by the builder"),
+            last_used: AtomicInstant::new(now),
+        });
+
+        entry.ratelimiter.try_wait()
+    }
+
+    /// Returns the number of tokens currently available for the key, or
+    /// `None` if the key does not currently have a token bucket.
+    pub fn available(&self, key: &K) -> Option<u64> {
+        self.shards[self.shard(key)]
+            .read()
+            .entries
+            .get(key)
+            .map(|entry| entry.ratelimiter.available())
+    }
+
+    /// Removes all keys which have not been used within the idle timeout. Idle
+    /// keys are also evicted from a shard when a new key is added to it, at
+    /// most once per idle timeout, so calling this is only necessary to
+    /// reclaim memory promptly or when no new keys are being seen. Does
+    /// nothing if no idle timeout is configured.
+    pub fn evict_idle(&self) {
+        if let Some(idle_timeout) = self.idle_timeout {
+            let now = Instant::now();
+
+            for shard in self.shards.iter() {
+                shard.write().sweep(now, idle_timeout);
+            }
+        }
+    }
+
+    /// Returns the number of keys which currently have a token bucket.
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().entries.len())
+            .sum()
+    }
+
+    /// Returns true if there are no keys with a token bucket.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Internal function to select the shard for a key.
+    fn shard(&self, key: &K) -> usize {
+        (self.hasher.hash_one(key) % self.shards.len() as u64) as usize
+    }
+}
+
+pub struct KeyedBuilder<K> {
+    builder: Builder,
+    idle_timeout: Option<core::time::Duration>,
+    shards: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K: Hash + Eq + Clone> KeyedBuilder<K> {
+    /// Initialize a new builder that will add `amount` tokens to each key's
+    /// token bucket after each `interval` has elapsed.
+    fn new(amount: u64, interval: core::time::Duration) -> Self {
+        Self {
+            builder: Builder::new(amount, interval),
+            // default of no eviction
+            idle_timeout: None,
+            shards: 16,
+            _key: PhantomData,
+        }
+    }
+
+    /// Set the max tokens that can be held in the token bucket for each key.
+    ///
+    /// See [`crate::Builder::max_tokens`] for details.
+    pub fn max_tokens(mut self, tokens: u64) -> Self {
+        self.builder = self.builder.max_tokens(tokens);
+        self
+    }
+
+    /// Set the number of tokens that are initially available when a token
+    /// bucket is created for a key.
+    ///
+    /// See [`crate::Builder::initial_available`] for details.
+    pub fn initial_available(mut self, tokens: u64) -> Self {
+        self.builder = self.builder.initial_available(tokens);
+        self
+    }
+
+    /// Set the mode, which determines how tokens accumulate for each key while
+    /// the key is idle.
+    ///
+    /// See [`crate::Builder::mode`] for details.
+    pub fn mode(mut self, mode: RatelimitMode) -> Self {
+        self.builder = self.builder.mode(mode);
+        self
+    }
+
+    /// Set a duration after which an unused key will be evicted. Without an
+    /// idle timeout, the number of tracked keys grows without bound.
+    ///
+    /// The default is that keys are never evicted.
+    pub fn idle_timeout(mut self, timeout: core::time::Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the number of shards the keys are spread across. More shards
+    /// reduce lock contention when many threads are acquiring tokens for
+    /// different keys.
+    ///
+    /// The default is 16 shards. Values less than one are treated as one.
+    pub fn shards(mut self, shards: usize) -> Self {
+        self.shards = shards;
+        self
+    }
+
+    /// Consumes this `KeyedBuilder` and attempts to construct a
+    /// `KeyedRatelimiter`.
+    pub fn build(self) -> Result<KeyedRatelimiter<K>, Error> {
+        // validate the parameters by constructing a single token bucket
+        let _ = self.builder.clone().build()?;
+
+        let idle_timeout = match self.idle_timeout {
+            Some(timeout) => {
+                if timeout.as_nanos() > u64::MAX as u128 {
+                    return Err(Error::IdleTimeoutTooLong);
+                }
+                Some(Duration::from_nanos(timeout.as_nanos() as u64))
+            }
+            None => None,
+        };
+
+        // the first sweep of each shard happens one idle timeout after build
+        let next_sweep = Instant::now() + idle_timeout.unwrap_or_default();
+
+        let shards = (0..self.shards.max(1))
+            .map(|_| {
+                RwLock::new(Shard {
+                    entries: HashMap::new(),
+                    next_sweep,
+                })
+            })
+            .collect();
+
+        Ok(KeyedRatelimiter {
+            builder: self.builder,
+            hasher: RandomState::new(),
+            idle_timeout,
+            shards,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::time::Duration;
+
+    // test that each key has an independent token bucket
+    #[test]
+    pub fn independent() {
+        let rl = KeyedRatelimiter::builder(1, Duration::from_secs(60))
+            .initial_available(2)
+            .max_tokens(2)
+            .build()
+            .unwrap();
+
+        assert!(rl.is_empty());
+        assert_eq!(rl.available(&1), None);
+
+        assert!(rl.try_wait(&1).is_ok());
+        assert!(rl.try_wait(&1).is_ok());
+        assert!(rl.try_wait(&1).is_err());
+
+        assert!(rl.try_wait(&2).is_ok());
+        assert_eq!(rl.available(&1), Some(0));
+        assert_eq!(rl.available(&2), Some(1));
+        assert_eq!(rl.len(), 2);
+    }
+
+    // test that idle keys are evicted
+    #[test]
+    pub fn idle_timeout() {
+        let rl = KeyedRatelimiter::builder(1, Duration::from_secs(60))
+            .initial_available(1)
+            .idle_timeout(Duration::from_millis(10))
+            .shards(1)
+            .build()
+            .unwrap();
+
+        assert!(rl.try_wait(&1).is_ok());
+        assert!(rl.try_wait(&1).is_err());
+
+        std::thread::sleep(Duration::from_millis(20));
+
+        // adding a new key evicts the idle key from the shard
+        assert!(rl.try_wait(&2).is_ok());
+        assert_eq!(rl.len(), 1);
+
+        // the evicted key gets a fresh token bucket
+        assert!(rl.try_wait(&1).is_ok());
+
+        std::thread::sleep(Duration::from_millis(20));
+        rl.evict_idle();
+        assert!(rl.is_empty());
+    }
+
+    // test that invalid parameters are rejected
+    #[test]
+    pub fn invalid() {
+        assert_eq!(
+            KeyedRatelimiter::<u64>::builder(10, Duration::from_secs(1))
+                .max_tokens(1)
+                .build()
+                .err(),
+            Some(Error::MaxTokensTooLow)
+        );
+    }
+}
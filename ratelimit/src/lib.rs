@@ -33,7 +33,6 @@
 //! // here generates 50 million tokens/s and allows no more than 50 tokens to
 //! // be acquired in any 1 microsecond long window.
 //! let ratelimiter = Ratelimiter::builder(50, Duration::from_micros(1))
-//!     .max_tokens(50)
 //!     .build()
 //!     .unwrap();
 //!
@@ -54,10 +53,14 @@
 //! ```
 
 use clocksource::precise::{AtomicInstant, Duration, Instant};
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use parking_lot::RwLock;
 use thiserror::Error;
 
+mod keyed;
+
+pub use keyed::{KeyedBuilder, KeyedRatelimiter};
+
 #[derive(Error, Debug, PartialEq, Eq)]
 pub enum Error {
     #[error("available tokens cannot be set higher than max tokens")]
@@ -68,6 +71,27 @@ pub enum Error {
     RefillAmountTooHigh,
     #[error("refill interval in nanoseconds exceeds maximum u64")]
     RefillIntervalTooLong,
+    #[error("idle timeout in nanoseconds exceeds maximum u64")]
+    IdleTimeoutTooLong,
+    #[error("requested tokens exceed the max tokens")]
+    TokensExceedMaxTokens,
+    #[error("requested tokens cannot be acquired before the deadline")]
+    DeadlineUnreachable,
+}
+
+/// Determines how tokens accumulate while the ratelimiter is not being used.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum RatelimitMode {
+    /// Tokens accumulate up to the max tokens, so after an idle period a burst
+    /// of up to the max tokens may be acquired at once.
+    #[default]
+    TokenBucket,
+    /// Tokens never accumulate beyond a single refill, regardless of the max
+    /// tokens. After an idle period, at most one refill's worth of tokens may
+    /// be acquired before the rate is enforced again. Tokens which would have
+    /// accumulated are counted as dropped. This smooths traffic to the refill
+    /// rate at the cost of never allowing bursts.
+    LeakyBucket,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -75,13 +99,18 @@ struct Parameters {
     capacity: u64,
     refill_amount: u64,
     refill_interval: Duration,
+    mode: RatelimitMode,
 }
 
+type RefillCallback = Box<dyn Fn(u64) + Send + Sync>;
+
 pub struct Ratelimiter {
     available: AtomicU64,
     dropped: AtomicU64,
     parameters: RwLock<Parameters>,
     refill_at: AtomicInstant,
+    on_refill: RwLock<Option<RefillCallback>>,
+    has_on_refill: AtomicBool,
 }
 
 impl Ratelimiter {
@@ -97,6 +126,37 @@ impl Ratelimiter {
         Builder::new(amount, interval)
     }
 
+    /// Initialize a builder that will construct a `Ratelimiter` which allows
+    /// `count` tokens per `window`, such as 5000 tokens per minute. The max
+    /// tokens is set to `count`, so the whole budget for a window may be used
+    /// in a single burst.
+    ///
+    /// The rate is decomposed into a refill amount and interval. The amount is
+    /// the smallest number of tokens which keeps the interval at or above 1
+    /// microsecond, as recommended for [`Ratelimiter::builder`], and the
+    /// interval is `window * amount / count`, rounded to the nearest
+    /// nanosecond. The rounding limits the precision of the effective rate to
+    /// within 0.05% of the target, with the error shrinking as the interval
+    /// grows. For windows shorter than 1 microsecond, the whole count is added
+    /// once per window.
+    pub fn per_duration(count: u64, window: core::time::Duration) -> Builder {
+        let window_ns = window.as_nanos();
+
+        let (amount, interval_ns) = if count == 0 {
+            (0, window_ns)
+        } else {
+            let count = count as u128;
+            let amount = (count * 1000).div_ceil(window_ns.max(1)).clamp(1, count);
+            let interval_ns = ((window_ns * amount + count / 2) / count).max(1);
+
+            (amount as u64, interval_ns)
+        };
+
+        let interval = core::time::Duration::from_nanos(interval_ns.try_into().unwrap_or(u64::MAX));
+
+        Builder::new(amount, interval).max_tokens(count)
+    }
+
     /// Return the current effective rate of the Ratelimiter in tokens/second
     pub fn rate(&self) -> f64 {
         let parameters = self.parameters.read();
@@ -153,6 +213,9 @@ impl Ratelimiter {
     /// Allows for changing the maximum number of tokens that can be held by the
     /// ratelimiter for immediate use. This effectively sets the burst size. The
     /// configured value must be greater than or equal to the refill amount.
+    ///
+    /// See [`Ratelimiter::set_max_tokens_scaled`] to instead scale the
+    /// available tokens in proportion to the change.
     pub fn set_max_tokens(&self, amount: u64) -> Result<(), Error> {
         let mut parameters = self.parameters.write();
 
@@ -178,11 +241,67 @@ impl Ratelimiter {
         }
     }
 
+    /// Changes the maximum number of tokens that can be held by the ratelimiter
+    /// and scales the available tokens in proportion to the change. For
+    /// example, halving the maximum tokens will also halve the available
+    /// tokens, and doubling it will double them. The scaled amount is rounded
+    /// down.
+    ///
+    /// Unlike [`Ratelimiter::set_max_tokens`], which raises the available
+    /// tokens to a higher maximum, this keeps the bucket at the same fraction
+    /// of its capacity. The configured value must be greater than or equal to
+    /// the refill amount.
+    pub fn set_max_tokens_scaled(&self, amount: u64) -> Result<(), Error> {
+        let mut parameters = self.parameters.write();
+
+        if amount < parameters.refill_amount {
+            return Err(Error::MaxTokensTooLow);
+        }
+
+        let previous = parameters.capacity;
+        parameters.capacity = amount;
+
+        loop {
+            let available = self.available();
+
+            let scaled = if previous == 0 {
+                0
+            } else {
+                (available as u128 * amount as u128 / previous as u128).min(amount as u128) as u64
+            };
+
+            if self
+                .available
+                .compare_exchange(available, scaled, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns the number of tokens currently available.
     pub fn available(&self) -> u64 {
         self.available.load(Ordering::Relaxed)
     }
 
+    /// Returns true if the provided number of tokens could be acquired right
+    /// now, without consuming any tokens.
+    ///
+    /// Unlike [`Ratelimiter::available`], this first performs any refill which
+    /// is due, in the same way as `try_wait`, so it does not under-report the
+    /// available tokens just after a refill boundary has passed.
+    ///
+    /// This is only a hint. Concurrent callers may consume the tokens before
+    /// they are acquired.
+    pub fn would_allow(&self, tokens: u64) -> bool {
+        let _ = self.refill(Instant::now());
+
+        self.available() >= tokens
+    }
+
     /// Returns the time of the next refill.
     pub fn next_refill(&self) -> Instant {
         self.refill_at.load(Ordering::Relaxed)
@@ -250,20 +369,53 @@ impl Ratelimiter {
 
         let available = self.available.load(Ordering::Acquire);
 
-        if available + amount >= parameters.capacity {
+        // in leaky bucket mode, tokens may only accumulate up to one refill
+        let capacity = match parameters.mode {
+            RatelimitMode::TokenBucket => parameters.capacity,
+            RatelimitMode::LeakyBucket => parameters.capacity.min(parameters.refill_amount),
+        };
+
+        let added = if available + amount >= capacity {
             // we will fill the bucket up to the capacity
-            let to_add = parameters.capacity - available;
+            let to_add = capacity.saturating_sub(available);
             self.available.fetch_add(to_add, Ordering::Release);
 
             // and increment the number of tokens dropped
             self.dropped.fetch_add(amount - to_add, Ordering::Relaxed);
+
+            to_add
         } else {
             self.available.fetch_add(amount, Ordering::Release);
+
+            amount
+        };
+
+        // release the parameters so the callback may change them
+        drop(parameters);
+
+        // avoid taking the lock unless a callback has been set
+        if added > 0 && self.has_on_refill.load(Ordering::Acquire) {
+            if let Some(callback) = self.on_refill.read().as_ref() {
+                callback(added);
+            }
         }
 
         Ok(())
     }
 
+    /// Sets a callback which is invoked after each refill which adds tokens,
+    /// with the number of tokens which were added. This replaces any
+    /// previously set callback.
+    ///
+    /// Refills happen lazily, so the callback runs on the calling thread during
+    /// `try_wait` or any other method which performs a refill. It must be
+    /// cheap and must not block, as it runs on the hot path of acquiring
+    /// tokens. The callback must not set a new callback, which would deadlock.
+    pub fn on_refill(&self, f: impl Fn(u64) + Send + Sync + 'static) {
+        *self.on_refill.write() = Some(Box::new(f));
+        self.has_on_refill.store(true, Ordering::Release);
+    }
+
     /// Non-blocking function to "wait" for a single token. On success, a single
     /// token has been acquired. On failure, a `Duration` hinting at when the
     /// next refill would occur is returned.
@@ -342,13 +494,95 @@ impl Ratelimiter {
             }
         }
     }
+
+    /// Blocking function to wait for multiple tokens, giving up at a deadline.
+    /// On success, all of the tokens have been acquired at once.
+    ///
+    /// Before waiting, the time at which enough tokens will have accumulated is
+    /// computed from the refill rate. If this is after the deadline, an error
+    /// is returned immediately rather than waiting for the deadline to pass.
+    /// An error is also returned if the number of tokens could never be
+    /// available at once because it exceeds the max tokens.
+    ///
+    /// Concurrent callers may take tokens while this function is waiting. In
+    /// that case the estimate is recomputed, so this function may still return
+    /// an error once it becomes clear that the deadline can no longer be met.
+    pub fn try_wait_n_until(&self, tokens: u64, deadline: Instant) -> Result<(), Error> {
+        {
+            let parameters = self.parameters.read();
+
+            let capacity = match parameters.mode {
+                RatelimitMode::TokenBucket => parameters.capacity,
+                RatelimitMode::LeakyBucket => parameters.capacity.min(parameters.refill_amount),
+            };
+
+            if tokens > capacity {
+                return Err(Error::TokensExceedMaxTokens);
+            }
+        }
+
+        loop {
+            let now = Instant::now();
+            let _ = self.refill(now);
+
+            let available = self.available.load(Ordering::Acquire);
+
+            if available >= tokens {
+                if self
+                    .available
+                    .compare_exchange(
+                        available,
+                        available - tokens,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                    )
+                    .is_ok()
+                {
+                    return Ok(());
+                }
+
+                // we raced with another caller, try again
+                continue;
+            }
+
+            // determine when enough refills will have occurred to cover the
+            // shortfall
+            let ready_at = {
+                let parameters = self.parameters.read();
+
+                // without refills, the shortfall can never be covered
+                if parameters.refill_amount == 0 {
+                    return Err(Error::DeadlineUnreachable);
+                }
+
+                let refills = (tokens - available).div_ceil(parameters.refill_amount);
+
+                self.next_refill()
+                    + Duration::from_nanos(
+                        (refills - 1).saturating_mul(parameters.refill_interval.as_nanos()),
+                    )
+            };
+
+            if ready_at > deadline {
+                return Err(Error::DeadlineUnreachable);
+            }
+
+            if ready_at > now {
+                std::thread::sleep(core::time::Duration::from_nanos(
+                    (ready_at - now).as_nanos(),
+                ));
+            }
+        }
+    }
 }
 
+#[derive(Clone)]
 pub struct Builder {
     initial_available: u64,
     max_tokens: u64,
     refill_amount: u64,
     refill_interval: core::time::Duration,
+    mode: RatelimitMode,
 }
 
 impl Builder {
@@ -358,10 +592,12 @@ impl Builder {
         Self {
             // default of zero tokens initially
             initial_available: 0,
-            // default of one to prohibit bursts
-            max_tokens: 1,
+            // default to the refill amount to prohibit bursts beyond a single
+            // refill
+            max_tokens: amount.max(1),
             refill_amount: amount,
             refill_interval: interval,
+            mode: RatelimitMode::TokenBucket,
         }
     }
 
@@ -369,8 +605,10 @@ impl Builder {
     /// time. This limits the size of any bursts by placing an upper bound on
     /// the number of tokens available for immediate use.
     ///
-    /// By default, the max_tokens will be set to one unless the refill amount
-    /// requires a higher value.
+    /// By default, the max_tokens will be set to the refill amount (or one if
+    /// the refill amount is zero). This is the minimum valid value and means
+    /// no tokens can accumulate beyond a single refill. Use this method to
+    /// raise the limit and allow larger bursts.
     ///
     /// The selected value cannot be lower than the refill amount.
     pub fn max_tokens(mut self, tokens: u64) -> Self {
@@ -392,6 +630,15 @@ impl Builder {
         self
     }
 
+    /// Set the mode, which determines how tokens accumulate while the
+    /// ratelimiter is idle. See [`RatelimitMode`] for details.
+    ///
+    /// The default is `RatelimitMode::TokenBucket`.
+    pub fn mode(mut self, mode: RatelimitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
     /// Consumes this `Builder` and attempts to construct a `Ratelimiter`.
     pub fn build(self) -> Result<Ratelimiter, Error> {
         if self.max_tokens < self.refill_amount {
@@ -408,6 +655,7 @@ impl Builder {
             capacity: self.max_tokens,
             refill_amount: self.refill_amount,
             refill_interval: Duration::from_nanos(self.refill_interval.as_nanos() as u64),
+            mode: self.mode,
         };
 
         let refill_at = AtomicInstant::new(Instant::now() + self.refill_interval);
@@ -417,6 +665,8 @@ impl Builder {
             dropped: AtomicU64::new(0),
             parameters: parameters.into(),
             refill_at,
+            on_refill: RwLock::new(None),
+            has_on_refill: AtomicBool::new(false),
         })
     }
 }
@@ -447,6 +697,36 @@ mod tests {
         approx_eq!(rl.rate(), 12012012.0);
     }
 
+    // test that the effective rate matches the target rate per duration
+    #[test]
+    pub fn per_duration() {
+        let rl = Ratelimiter::per_duration(5000, Duration::from_secs(60))
+            .build()
+            .unwrap();
+        approx_eq!(rl.rate(), 5000.0 / 60.0);
+        assert_eq!(rl.max_tokens(), 5000);
+        assert_eq!(rl.refill_amount(), 1);
+
+        let rl = Ratelimiter::per_duration(3, Duration::from_secs(1))
+            .build()
+            .unwrap();
+        approx_eq!(rl.rate(), 3.0);
+
+        // high rates add multiple tokens per interval
+        let rl = Ratelimiter::per_duration(50_000_000, Duration::from_secs(1))
+            .build()
+            .unwrap();
+        approx_eq!(rl.rate(), 50_000_000.0);
+        assert_eq!(rl.refill_amount(), 50);
+        assert_eq!(rl.refill_interval(), Duration::from_micros(1));
+
+        let rl = Ratelimiter::per_duration(7_777_777, Duration::from_secs(1))
+            .build()
+            .unwrap();
+        approx_eq!(rl.rate(), 7_777_777.0);
+        assert!(rl.refill_interval() >= Duration::from_micros(1));
+    }
+
     // quick test that a ratelimiter yields tokens at the desired rate
     #[test]
     pub fn wait() {
@@ -488,6 +768,191 @@ mod tests {
         assert!(rl.next_refill() < clocksource::precise::Instant::now());
     }
 
+    // test that max tokens defaults to the refill amount
+    #[test]
+    pub fn default_max_tokens() {
+        let rl = Ratelimiter::builder(1, Duration::from_millis(1))
+            .build()
+            .unwrap();
+        assert_eq!(rl.max_tokens(), 1);
+
+        let rl = Ratelimiter::builder(50, Duration::from_micros(1))
+            .build()
+            .unwrap();
+        assert_eq!(rl.max_tokens(), 50);
+
+        let rl = Ratelimiter::builder(50, Duration::from_micros(1))
+            .max_tokens(100)
+            .build()
+            .unwrap();
+        assert_eq!(rl.max_tokens(), 100);
+
+        assert!(Ratelimiter::builder(50, Duration::from_micros(1))
+            .max_tokens(10)
+            .build()
+            .is_err());
+    }
+
+    // test that raising max tokens raises the available tokens
+    #[test]
+    pub fn set_max_tokens() {
+        let rl = Ratelimiter::builder(1, Duration::from_secs(60))
+            .max_tokens(10)
+            .initial_available(8)
+            .build()
+            .unwrap();
+
+        rl.set_max_tokens(20).unwrap();
+        assert_eq!(rl.max_tokens(), 20);
+        assert_eq!(rl.available(), 20);
+
+        assert_eq!(rl.set_max_tokens(0), Err(Error::MaxTokensTooLow));
+    }
+
+    // test that scaling the max tokens scales the available tokens
+    #[test]
+    pub fn set_max_tokens_scaled() {
+        let rl = Ratelimiter::builder(1, Duration::from_secs(60))
+            .max_tokens(10)
+            .initial_available(8)
+            .build()
+            .unwrap();
+
+        // raising
+        rl.set_max_tokens_scaled(20).unwrap();
+        assert_eq!(rl.max_tokens(), 20);
+        assert_eq!(rl.available(), 16);
+
+        // lowering
+        rl.set_max_tokens_scaled(5).unwrap();
+        assert_eq!(rl.max_tokens(), 5);
+        assert_eq!(rl.available(), 4);
+
+        // rounds down
+        rl.set_max_tokens_scaled(3).unwrap();
+        assert_eq!(rl.available(), 2);
+
+        assert_eq!(rl.set_max_tokens_scaled(0), Err(Error::MaxTokensTooLow));
+        assert_eq!(rl.max_tokens(), 3);
+    }
+
+    // test that would_allow accounts for refills without consuming tokens
+    #[test]
+    pub fn would_allow() {
+        let rl = Ratelimiter::builder(2, Duration::from_millis(10))
+            .max_tokens(4)
+            .initial_available(1)
+            .build()
+            .unwrap();
+
+        assert!(rl.would_allow(1));
+        assert!(!rl.would_allow(2));
+        assert_eq!(rl.available(), 1);
+
+        assert!(rl.try_wait().is_ok());
+        assert!(!rl.would_allow(1));
+
+        // a refill is due, but has not yet been performed
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(rl.available(), 0);
+        assert!(rl.would_allow(4));
+        assert!(!rl.would_allow(5));
+        assert_eq!(rl.available(), 4);
+    }
+
+    // test that only the token bucket mode allows a burst after being idle
+    #[test]
+    pub fn mode() {
+        let token = Ratelimiter::builder(1, Duration::from_millis(1))
+            .max_tokens(10)
+            .build()
+            .unwrap();
+        let leaky = Ratelimiter::builder(1, Duration::from_millis(1))
+            .max_tokens(10)
+            .mode(RatelimitMode::LeakyBucket)
+            .build()
+            .unwrap();
+
+        std::thread::sleep(Duration::from_millis(20));
+
+        for _ in 0..10 {
+            assert!(token.try_wait().is_ok());
+        }
+        assert!(token.try_wait().is_err());
+
+        assert!(leaky.try_wait().is_ok());
+        assert!(leaky.try_wait().is_err());
+        assert!(leaky.dropped() >= 18);
+    }
+
+    // test that waiting for multiple tokens respects the deadline
+    #[test]
+    pub fn try_wait_n_until() {
+        let rl = Ratelimiter::builder(1, Duration::from_millis(10))
+            .max_tokens(10)
+            .initial_available(1)
+            .build()
+            .unwrap();
+
+        let now = clocksource::precise::Instant::now();
+
+        assert_eq!(
+            rl.try_wait_n_until(11, now + Duration::from_secs(10)),
+            Err(Error::TokensExceedMaxTokens)
+        );
+
+        // the deadline is too soon, so this fails without waiting
+        let start = Instant::now();
+        assert_eq!(
+            rl.try_wait_n_until(5, now + Duration::from_millis(5)),
+            Err(Error::DeadlineUnreachable)
+        );
+        assert!(start.elapsed() < Duration::from_millis(5));
+        assert_eq!(rl.available(), 1);
+
+        // waits for two refills to acquire three tokens
+        let start = Instant::now();
+        assert_eq!(
+            rl.try_wait_n_until(3, now + Duration::from_millis(500)),
+            Ok(())
+        );
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        assert_eq!(rl.available(), 0);
+    }
+
+    // test that the refill callback is passed the number of tokens added
+    #[test]
+    pub fn on_refill() {
+        use std::sync::Arc;
+
+        let rl = Ratelimiter::builder(2, Duration::from_millis(10))
+            .max_tokens(2)
+            .build()
+            .unwrap();
+
+        let added = Arc::new(AtomicU64::new(0));
+        let calls = Arc::new(AtomicU64::new(0));
+        {
+            let added = added.clone();
+            let calls = calls.clone();
+            rl.on_refill(move |tokens| {
+                added.fetch_add(tokens, Ordering::Relaxed);
+                calls.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+
+        std::thread::sleep(Duration::from_millis(15));
+        assert!(rl.try_wait().is_ok());
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        assert_eq!(added.load(Ordering::Relaxed), 2);
+
+        // only the tokens which fit within the max tokens are reported
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(rl.try_wait().is_ok());
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+        assert_eq!(added.load(Ordering::Relaxed), 3);
+    }
+
     // quick test that capacity acts as expected
     #[test]
     pub fn capacity() {
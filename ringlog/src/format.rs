@@ -12,19 +12,25 @@ pub type FormatFunction = fn(
     record: &Record,
 ) -> Result<(), std::io::Error>;
 
+/// The default format, which includes the time, level, and module path of the
+/// log message. Any structured key-value fields on the record, such as those
+/// added with the `kvlog!` macro, are appended after the message as
+/// `key=value` pairs.
 pub fn default_format(
     w: &mut dyn std::io::Write,
     now: DateTime,
     record: &Record,
 ) -> Result<(), std::io::Error> {
-    writeln!(
+    write!(
         w,
         "{} {} [{}] {}",
         now,
         record.level(),
         record.module_path().unwrap_or("<unnamed>"),
         record.args()
-    )
+    )?;
+    write_key_values(w, record)?;
+    writeln!(w)
 }
 
 pub fn klog_format(
@@ -34,3 +40,69 @@ pub fn klog_format(
 ) -> Result<(), std::io::Error> {
     writeln!(w, "{} {}", now, record.args())
 }
+
+/// Writes any structured key-value fields on the record as space separated
+/// `key=value` pairs, each preceded by a space. Custom format functions may use
+/// this, or may visit `record.key_values()` directly to emit the fields in a
+/// structured form.
+pub fn write_key_values(w: &mut dyn std::io::Write, record: &Record) -> Result<(), std::io::Error> {
+    struct Visitor<'a>(&'a mut dyn std::io::Write);
+
+    impl<'kvs> kv::VisitSource<'kvs> for Visitor<'_> {
+        fn visit_pair(
+            &mut self,
+            key: kv::Key<'kvs>,
+            value: kv::Value<'kvs>,
+        ) -> Result<(), kv::Error> {
+            write!(self.0, " {key}={value}").map_err(kv::Error::boxed)
+        }
+    }
+
+    record
+        .key_values()
+        .visit(&mut Visitor(w))
+        .map_err(std::io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(record: &Record) -> (DateTime, String) {
+        let now = DateTime::from(clocksource::precise::UnixInstant::now());
+        let mut buffer = Vec::new();
+        default_format(&mut buffer, now, record).unwrap();
+        (now, String::from_utf8(buffer).unwrap())
+    }
+
+    // test that key-value fields are appended after the message
+    #[test]
+    fn default_format_key_values() {
+        let kvs = [("key", "value"), ("key2", "value2")];
+        let (now, formatted) = format(
+            &Record::builder()
+                .level(Level::Info)
+                .module_path(Some("app"))
+                .args(format_args!("message"))
+                .key_values(&kvs)
+                .build(),
+        );
+        assert_eq!(
+            formatted,
+            format!("{now} INFO [app] message key=value key2=value2\n")
+        );
+    }
+
+    // test that records without key-value fields are formatted unchanged
+    #[test]
+    fn default_format_no_key_values() {
+        let (now, formatted) = format(
+            &Record::builder()
+                .level(Level::Info)
+                .module_path(Some("app"))
+                .args(format_args!("message"))
+                .build(),
+        );
+        assert_eq!(formatted, format!("{now} INFO [app] message\n"));
+    }
+}
@@ -0,0 +1,76 @@
+use crate::*;
+
+/// A `DrainGuard` wraps a `Drain` and performs a final flush when it is
+/// dropped. This ensures log messages which are still queued when the program
+/// exits are written to the `Output`, rather than being lost because no final
+/// flush was run.
+///
+/// The guard can be flushed periodically in the same way as the `Drain` it
+/// wraps. Note that the final flush runs synchronously in `drop`, which adds
+/// shutdown latency proportional to the number of queued log messages, up to
+/// the depth of the log queue. Any error from the final flush is ignored.
+pub struct DrainGuard {
+    drain: Box<dyn Drain>,
+}
+
+impl DrainGuard {
+    /// Wrap a `Drain`, such as the one returned by `RingLog::start`, so that it
+    /// is flushed when dropped.
+    pub fn new(drain: Box<dyn Drain>) -> Self {
+        Self { drain }
+    }
+}
+
+impl From<Box<dyn Drain>> for DrainGuard {
+    fn from(drain: Box<dyn Drain>) -> Self {
+        Self::new(drain)
+    }
+}
+
+impl Drain for DrainGuard {
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        self.drain.flush()
+    }
+}
+
+impl Drop for DrainGuard {
+    fn drop(&mut self) {
+        let _ = self.drain.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test that queued messages are written to the output when the guard is
+    // dropped
+    #[test]
+    fn flush_on_drop() {
+        let dir = std::env::temp_dir();
+        let active = dir.join(format!("ringlog-guard-{}.log", std::process::id()));
+        let backup = dir.join(format!("ringlog-guard-{}.old", std::process::id()));
+
+        let (logger, drain) = LogBuilder::new()
+            .output(Box::new(File::new(&active, &backup, 1024 * 1024).unwrap()))
+            .build_raw()
+            .unwrap();
+        let guard = DrainGuard::new(Box::new(drain));
+
+        logger.log(
+            &Record::builder()
+                .level(Level::Info)
+                .args(format_args!("queued before shutdown"))
+                .build(),
+        );
+
+        // the message is still queued until the drain is flushed
+        assert!(std::fs::read_to_string(&active).unwrap().is_empty());
+
+        drop(guard);
+
+        let contents = std::fs::read_to_string(&active).unwrap();
+        let _ = std::fs::remove_file(&active);
+        assert!(contents.contains("queued before shutdown"));
+    }
+}
@@ -10,7 +10,9 @@
 //! `RingLog`, it can be registered as the global logger using the `start`
 //! method. You will be left with a `Box<dyn Drain>` which should be
 //! periodically flushed outside of any critical path. For example, in an admin
-//! thread or dedicated logging thread.
+//! thread or dedicated logging thread. The drain can be wrapped in a
+//! `DrainGuard` to perform a final flush when it is dropped, so that queued log
+//! messages are not lost on shutdown.
 //!
 //! For logging to a single file, the `LogBuilder` type can be used to construct
 //! an `RingLog` which has low overhead, but directs log messages to a single
@@ -26,7 +28,9 @@
 //! be routed to that instance of `RingLog`. Log messages that do not match any
 //! specific target will be routed to the default `RingLog` that has been added
 //! to the `MultiLogBuilder`. If there is no default, messages that do not match
-//! any specific target will be simply dropped.
+//! any specific target will be simply dropped. Level sinks can also be added
+//! to a `MultiLogBuilder` to additionally route log messages at or above a
+//! level to another `RingLog`, regardless of their `target`.
 //!
 //! This combination of logging types allows us to compose a logging backend
 //! which meets the application's needs. For example, you can use a local log
@@ -37,6 +41,7 @@
 pub use log::*;
 
 mod format;
+mod guard;
 #[macro_use]
 mod macros;
 mod multi;
@@ -47,6 +52,7 @@ mod single;
 mod traits;
 
 pub use format::*;
+pub use guard::*;
 pub use multi::*;
 pub use nop::*;
 pub use outputs::*;
@@ -15,6 +15,53 @@ macro_rules! fatal {
         );
 }
 
+#[macro_export]
+/// Logs a message with structured key-value fields. The fields are attached to
+/// the log record separately from the message, so a format function can emit
+/// them as distinct fields rather than interpolating them into the message.
+/// See `default_format` for how the fields are written by default.
+///
+/// The message is a format string literal, optionally followed by format
+/// arguments, and then one or more `key = value` fields. Any argument of the
+/// form `ident = expr` is treated as the start of the fields, so format
+/// arguments must come first.
+///
+/// The fields use the key-value support in the `log` crate, so this is
+/// equivalent to using the key-value syntax of the standard macros, for
+/// example `info!(key = value; "message")`. Records logged with the standard
+/// macros and no fields can be freely mixed with records logged using this
+/// macro, and are formatted as before.
+///
+/// A custom format function, for example one which writes JSON, can access the
+/// fields through `record.key_values()`. Either look up a known key with
+/// `record.key_values().get(key.into())`, or visit every field by implementing
+/// `log::kv::VisitSource`, as `write_key_values` does for the default format.
+///
+/// ```
+/// use ringlog::{kvlog, Level};
+///
+/// let status = 200;
+/// let latency = 1500;
+/// kvlog!(target: "access", Level::Info, "request complete", status = status, path = "/");
+/// kvlog!(Level::Warn, "slow request took {}ms", latency, status = status);
+/// ```
+macro_rules! kvlog {
+    // all the format arguments have been collected, only fields remain
+    (@args ($($target:tt)*) $level:expr, $fmt:literal, [$($arg:expr),*] $(, $key:ident = $value:expr)+ $(,)?) => (
+        $crate::log!($($target)* $level, $($key = $value),+; $fmt $(, $arg)*)
+    );
+    // collect the next format argument
+    (@args ($($target:tt)*) $level:expr, $fmt:literal, [$($arg:expr),*], $next:expr $(, $($rest:tt)*)?) => (
+        $crate::kvlog!(@args ($($target)*) $level, $fmt, [$($arg,)* $next] $(, $($rest)*)?)
+    );
+    (target: $target:expr, $level:expr, $fmt:literal $($rest:tt)+) => (
+        $crate::kvlog!(@args (target: $target,) $level, $fmt, [] $($rest)+)
+    );
+    ($level:expr, $fmt:literal $($rest:tt)+) => (
+        $crate::kvlog!(@args () $level, $fmt, [] $($rest)+)
+    );
+}
+
 #[cfg(feature = "metrics")]
 macro_rules! metrics {
     { $( $tt:tt )* } => { $( $tt )* }
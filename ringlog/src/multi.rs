@@ -10,6 +10,7 @@ use ahash::AHashMap as HashMap;
 pub(crate) struct MultiLogger {
     default: Option<Box<dyn Log>>,
     targets: HashMap<String, Box<dyn Log>>,
+    levels: Vec<(LevelFilter, Box<dyn Log>)>,
     level_filter: LevelFilter,
 }
 
@@ -20,6 +21,13 @@ impl MultiLogger {
             .map(|t| t.as_ref())
             .or(self.default.as_deref())
     }
+
+    fn get_levels(&self, level: Level) -> impl Iterator<Item = &dyn Log> {
+        self.levels
+            .iter()
+            .filter(move |(level_filter, _)| level <= *level_filter)
+            .map(|(_, log)| log.as_ref())
+    }
 }
 
 impl Log for MultiLogger {
@@ -28,8 +36,12 @@ impl Log for MultiLogger {
             false
         } else if let Some(target) = self.get_target(metadata.target()) {
             target.enabled(metadata)
+                || self
+                    .get_levels(metadata.level())
+                    .any(|log| log.enabled(metadata))
         } else {
-            false
+            self.get_levels(metadata.level())
+                .any(|log| log.enabled(metadata))
         }
     }
 
@@ -42,6 +54,11 @@ impl Log for MultiLogger {
                 target.log(record)
             }
         }
+        for log in self.get_levels(record.level()) {
+            if log.enabled(record.metadata()) {
+                log.log(record)
+            }
+        }
     }
 
     fn flush(&self) {}
@@ -50,6 +67,7 @@ impl Log for MultiLogger {
 pub(crate) struct MultiLogDrain {
     default: Option<Box<dyn Drain>>,
     targets: HashMap<String, Box<dyn Drain>>,
+    levels: Vec<Box<dyn Drain>>,
 }
 
 impl Drain for MultiLogDrain {
@@ -60,6 +78,9 @@ impl Drain for MultiLogDrain {
         for (_target, log_handle) in self.targets.iter_mut() {
             log_handle.flush()?;
         }
+        for log_handle in self.levels.iter_mut() {
+            log_handle.flush()?;
+        }
         Ok(())
     }
 }
@@ -68,9 +89,14 @@ impl Drain for MultiLogDrain {
 /// on the log's `target` metadata to a corresponding `RingLog`. Targets which
 /// do not match a specific target will be routed to the default `RingLog` if
 /// one is configured.
+///
+/// Level sinks may also be added, which receive every message at or above
+/// their level in addition to the target routing. See
+/// [`MultiLogBuilder::add_level_sink`] for details.
 pub struct MultiLogBuilder {
     default: Option<RingLog>,
     targets: HashMap<String, RingLog>,
+    levels: Vec<(LevelFilter, RingLog)>,
     level_filter: LevelFilter,
 }
 
@@ -79,6 +105,7 @@ impl Default for MultiLogBuilder {
         Self {
             default: None,
             targets: HashMap::new(),
+            levels: Vec::new(),
             level_filter: LevelFilter::Trace,
         }
     }
@@ -100,6 +127,22 @@ impl MultiLogBuilder {
         self
     }
 
+    /// Add a level sink which additionally receives every log message at or
+    /// above the provided level, independent of the `target` routing. For
+    /// example, adding a sink with `LevelFilter::Error` will send errors from
+    /// every target to that sink.
+    ///
+    /// Messages are duplicated, not diverted. A matching message is first
+    /// written to its target (or the default) log, and is then written to
+    /// each matching level sink in the order they were added. A message is
+    /// written to every matching level sink, so overlapping sinks will each
+    /// receive a copy. The builder's `level_filter` applies before any of the
+    /// routing, so messages it rejects never reach a level sink.
+    pub fn add_level_sink(mut self, level: LevelFilter, log: RingLog) -> Self {
+        self.levels.push((level, log));
+        self
+    }
+
     pub fn level_filter(mut self, level_filter: LevelFilter) -> Self {
         self.level_filter = level_filter;
         self
@@ -109,12 +152,14 @@ impl MultiLogBuilder {
         let mut loggers = MultiLogger {
             default: None,
             targets: HashMap::new(),
+            levels: Vec::new(),
             level_filter: self.level_filter,
         };
 
         let mut drains = MultiLogDrain {
             default: None,
             targets: HashMap::new(),
+            levels: Vec::new(),
         };
 
         if let Some(log) = self.default.take() {
@@ -127,6 +172,11 @@ impl MultiLogBuilder {
             drains.targets.insert(name.to_owned(), log.drain);
         }
 
+        for (level, log) in self.levels.drain(..) {
+            loggers.levels.push((level, log.logger));
+            drains.levels.push(log.drain);
+        }
+
         RingLog {
             logger: Box::new(loggers),
             drain: Box::new(drains),
@@ -134,3 +184,133 @@ impl MultiLogBuilder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// A logger which records the name of the log and the level of each
+    /// message it receives, in the order they are received.
+    struct Recorder {
+        name: &'static str,
+        level_filter: LevelFilter,
+        records: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Log for Recorder {
+        fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+            metadata.level() <= self.level_filter
+        }
+
+        fn log(&self, record: &log::Record<'_>) {
+            self.records
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", self.name, record.level()));
+        }
+
+        fn flush(&self) {}
+    }
+
+    fn recorder(
+        name: &'static str,
+        level_filter: LevelFilter,
+        records: &Arc<Mutex<Vec<String>>>,
+    ) -> RingLog {
+        RingLog {
+            logger: Box::new(Recorder {
+                name,
+                level_filter,
+                records: records.clone(),
+            }),
+            drain: Box::new(NopLogDrain {}),
+            level_filter,
+        }
+    }
+
+    fn log(log: &RingLog, level: Level, target: &str) {
+        log.logger.log(
+            &Record::builder()
+                .level(level)
+                .target(target)
+                .args(format_args!("message"))
+                .build(),
+        );
+    }
+
+    fn enabled(log: &RingLog, level: Level, target: &str) -> bool {
+        log.logger
+            .enabled(&Metadata::builder().level(level).target(target).build())
+    }
+
+    fn take(records: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+        std::mem::take(&mut *records.lock().unwrap())
+    }
+
+    // test that level sinks receive a copy of messages routed to a target
+    #[test]
+    fn level_sink() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let multi = MultiLogBuilder::new()
+            .add_target("app", recorder("app", LevelFilter::Trace, &records))
+            .add_level_sink(
+                LevelFilter::Error,
+                recorder("errors", LevelFilter::Trace, &records),
+            )
+            .build();
+
+        log(&multi, Level::Error, "app");
+        assert_eq!(take(&records), vec!["app ERROR", "errors ERROR"]);
+
+        // messages below the level of the sink only reach the target
+        log(&multi, Level::Info, "app");
+        assert_eq!(take(&records), vec!["app INFO"]);
+    }
+
+    // test that overlapping level sinks each receive a copy, in order
+    #[test]
+    fn overlapping_level_sinks() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let multi = MultiLogBuilder::new()
+            .default(recorder("default", LevelFilter::Trace, &records))
+            .add_level_sink(
+                LevelFilter::Warn,
+                recorder("first", LevelFilter::Trace, &records),
+            )
+            .add_level_sink(
+                LevelFilter::Error,
+                recorder("second", LevelFilter::Trace, &records),
+            )
+            .build();
+
+        log(&multi, Level::Error, "app");
+        assert_eq!(
+            take(&records),
+            vec!["default ERROR", "first ERROR", "second ERROR"]
+        );
+
+        log(&multi, Level::Warn, "app");
+        assert_eq!(take(&records), vec!["default WARN", "first WARN"]);
+    }
+
+    // test that a message accepted only by a level sink is enabled
+    #[test]
+    fn enabled_by_level_sink() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let multi = MultiLogBuilder::new()
+            .add_target("app", recorder("app", LevelFilter::Off, &records))
+            .add_level_sink(
+                LevelFilter::Error,
+                recorder("errors", LevelFilter::Trace, &records),
+            )
+            .build();
+
+        assert!(enabled(&multi, Level::Error, "app"));
+        assert!(enabled(&multi, Level::Error, "other"));
+        assert!(!enabled(&multi, Level::Info, "app"));
+
+        log(&multi, Level::Error, "app");
+        assert_eq!(take(&records), vec!["errors ERROR"]);
+    }
+}
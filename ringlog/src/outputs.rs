@@ -6,6 +6,7 @@ use crate::*;
 
 use std::io::{BufWriter, Error, Write};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 /// An output that writes to `stdout`.
 pub struct Stdout {
@@ -67,6 +68,26 @@ impl Write for Stderr {
 
 impl Output for Stderr {}
 
+/// Controls when a `File` output syncs the log file to durable storage.
+///
+/// Syncing ensures that logs which have been flushed survive a crash of the
+/// process or host, at the cost of waiting for the storage device on the flush
+/// path. Syncing on every flush gives the strongest guarantee but can greatly
+/// reduce the throughput of the drain, particularly on slow disks. Syncing on
+/// an interval bounds how much of the tail of the log may be lost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Never explicitly sync the file. Durability is left to the operating
+    /// system.
+    #[default]
+    Never,
+    /// Sync the file on every flush of the output.
+    EveryFlush,
+    /// Sync the file on a flush if at least this much time has passed since
+    /// the last sync.
+    Interval(Duration),
+}
+
 /// A file based output which allows rotating the current log file off to a
 /// backup location.
 pub struct File {
@@ -74,6 +95,8 @@ pub struct File {
     backup: PathBuf,
     max_size: u64,
     writer: BufWriter<std::fs::File>,
+    fsync: FsyncPolicy,
+    synced: Instant,
 }
 
 impl File {
@@ -101,14 +124,43 @@ impl File {
             backup: backup.as_ref().to_owned(),
             max_size,
             writer,
+            fsync: FsyncPolicy::Never,
+            synced: Instant::now(),
         })
     }
 
+    /// Set the policy for syncing the log file to durable storage. See
+    /// [`FsyncPolicy`] for the trade-offs.
+    ///
+    /// The default is to never explicitly sync the file.
+    pub fn fsync(mut self, policy: FsyncPolicy) -> Self {
+        self.fsync = policy;
+        self
+    }
+
     /// Return the current size of the live log in bytes.
     fn size(&self) -> Result<u64, Error> {
         Ok(self.writer.get_ref().metadata()?.len())
     }
 
+    /// Sync the live log to durable storage if required by the policy.
+    fn sync(&mut self) -> Result<(), Error> {
+        let now = Instant::now();
+
+        let sync = match self.fsync {
+            FsyncPolicy::Never => false,
+            FsyncPolicy::EveryFlush => true,
+            FsyncPolicy::Interval(interval) => now.duration_since(self.synced) >= interval,
+        };
+
+        if sync {
+            self.writer.get_ref().sync_data()?;
+            self.synced = now;
+        }
+
+        Ok(())
+    }
+
     /// Rotate the current log file if necessary.
     fn rotate(&mut self) -> Result<(), Error> {
         let size = self.size()?;
@@ -145,8 +197,92 @@ impl Write for File {
     }
     fn flush(&mut self) -> std::result::Result<(), Error> {
         self.writer.flush()?;
+        self.sync()?;
         self.rotate()
     }
 }
 
 impl Output for File {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a `File` output in a fresh temporary directory, returning the
+    /// output and the paths of the active and backup logs.
+    fn file(name: &str, max_size: u64, policy: FsyncPolicy) -> (File, PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("ringlog-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let active = dir.join("active.log");
+        let backup = dir.join("backup.log");
+        let file = File::new(&active, &backup, max_size).unwrap().fsync(policy);
+        (file, active, backup)
+    }
+
+    fn write_and_flush(file: &mut File) {
+        file.write_all(b"message\n").unwrap();
+        file.flush().unwrap();
+    }
+
+    #[test]
+    fn fsync_never() {
+        let (mut file, _, _) = file("fsync-never", 1024, FsyncPolicy::Never);
+        let synced = file.synced;
+
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(1));
+            write_and_flush(&mut file);
+            assert_eq!(file.synced, synced);
+        }
+    }
+
+    #[test]
+    fn fsync_every_flush() {
+        let (mut file, _, _) = file("fsync-every-flush", 1024, FsyncPolicy::EveryFlush);
+        let mut synced = file.synced;
+
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(1));
+            write_and_flush(&mut file);
+            assert!(file.synced > synced);
+            synced = file.synced;
+        }
+    }
+
+    #[test]
+    fn fsync_interval() {
+        let interval = Duration::from_millis(100);
+        let (mut file, _, _) = file("fsync-interval", 1024, FsyncPolicy::Interval(interval));
+        let synced = file.synced;
+
+        // the interval has not passed since the file was opened
+        write_and_flush(&mut file);
+        assert_eq!(file.synced, synced);
+
+        std::thread::sleep(interval);
+        write_and_flush(&mut file);
+        assert!(file.synced.duration_since(synced) >= interval);
+        let synced = file.synced;
+
+        // the interval has not passed since the last sync
+        write_and_flush(&mut file);
+        assert_eq!(file.synced, synced);
+    }
+
+    // test that the log is still rotated when it is synced on flush
+    #[test]
+    fn fsync_rotate() {
+        let (mut file, active, backup) = file("fsync-rotate", 8, FsyncPolicy::EveryFlush);
+
+        write_and_flush(&mut file);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "message\n");
+        assert!(std::fs::read_to_string(&active).unwrap().is_empty());
+
+        file.write_all(b"next\n").unwrap();
+        file.flush().unwrap();
+        assert_eq!(std::fs::read_to_string(&active).unwrap(), "next\n");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "message\n");
+    }
+}
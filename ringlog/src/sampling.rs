@@ -5,13 +5,38 @@
 use crate::*;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-/// Implements a logger which only logs 1 in N log messages.
-pub(crate) struct SamplingLogger {
-    logger: Logger,
+/// Tracks a count of log messages and decides which 1 in N are logged.
+struct Sampler {
     counter: AtomicUsize,
     sample: usize,
 }
 
+impl Sampler {
+    fn new(sample: usize) -> Self {
+        Self {
+            // initialize to 1 not 0 so the first fetch_add returns a 1
+            counter: AtomicUsize::new(1),
+            sample,
+        }
+    }
+
+    /// Returns true if this is the Nth message and it should be logged.
+    fn sample(&self) -> bool {
+        self.counter
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.sample)
+    }
+}
+
+/// Implements a logger which only logs 1 in N log messages. Levels with their
+/// own sampling rate are counted separately, while all other levels share a
+/// single counter.
+pub(crate) struct SamplingLogger {
+    logger: Logger,
+    sampler: Sampler,
+    level_samplers: [Option<Sampler>; 5],
+}
+
 impl SamplingLogger {
     pub fn level_filter(&self) -> LevelFilter {
         self.logger.level_filter()
@@ -29,11 +54,14 @@ impl Log for SamplingLogger {
             return;
         }
 
-        let count = self.counter.fetch_add(1, Ordering::Relaxed);
+        // levels start from 1 for `Error`
+        let sampler = self.level_samplers[record.level() as usize - 1]
+            .as_ref()
+            .unwrap_or(&self.sampler);
 
         // if this is the Nth message, we should log it
         #[allow(clippy::needless_else)]
-        if (count % self.sample) == 0 {
+        if sampler.sample() {
             self.logger.log(record)
         } else {
             metrics! {
@@ -50,6 +78,7 @@ impl Log for SamplingLogger {
 pub struct SamplingLogBuilder {
     log_builder: LogBuilder,
     sample: usize,
+    level_sample: [Option<usize>; 6],
 }
 
 impl Default for SamplingLogBuilder {
@@ -57,6 +86,7 @@ impl Default for SamplingLogBuilder {
         Self {
             log_builder: LogBuilder::default(),
             sample: 100,
+            level_sample: [None; 6],
         }
     }
 }
@@ -69,6 +99,11 @@ impl SamplingLogBuilder {
 
     /// Sets the depth of the log queue. Deeper queues are less likely to drop
     /// messages, but come at the cost of additional memory utilization.
+    ///
+    /// A buffer of `single_message_size` bytes is preallocated for every slot
+    /// in the queue, so the logger holds roughly `messages` x
+    /// `single_message_size` bytes of buffers. The default depth is 4096
+    /// messages.
     pub fn log_queue_depth(mut self, messages: usize) -> Self {
         self.log_builder = self.log_builder.log_queue_depth(messages);
         self
@@ -100,14 +135,29 @@ impl SamplingLogBuilder {
         self
     }
 
+    /// Sets the sampling to 1 in N requests for messages of a single level,
+    /// overriding the rate set with `sample` for that level. For example, a
+    /// rate of 1 can be used to keep every `Error` while `Debug` and `Info`
+    /// messages are heavily sampled.
+    ///
+    /// Each level with its own rate is sampled independently, while the other
+    /// levels share a single counter so that `sample` applies across all of
+    /// them. Setting a rate for `LevelFilter::Off` has no effect.
+    pub fn sample_rate_for_level(mut self, level: LevelFilter, sample: usize) -> Self {
+        self.level_sample[level as usize] = Some(sample);
+        self
+    }
+
     /// Consumes the builder and returns a configured `SamplingLogger` and `LogDrain`.
     pub(crate) fn build_raw(self) -> Result<(SamplingLogger, LogDrain), &'static str> {
         let (logger, log_handle) = self.log_builder.build_raw()?;
         let logger = SamplingLogger {
             logger,
-            // initialize to 1 not 0 so the first fetch_add returns a 1
-            counter: AtomicUsize::new(1),
-            sample: self.sample,
+            sampler: Sampler::new(self.sample),
+            // skip `LevelFilter::Off`, which has no messages
+            level_samplers: core::array::from_fn(|level| {
+                self.level_sample[level + 1].map(Sampler::new)
+            }),
         };
         Ok((logger, log_handle))
     }
@@ -123,3 +173,98 @@ impl SamplingLogBuilder {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// An `Output` which keeps everything written to it.
+    #[derive(Clone, Default)]
+    struct Capture {
+        buffer: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.buffer.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output for Capture {}
+
+    fn level_format(
+        w: &mut dyn std::io::Write,
+        _now: clocksource::datetime::DateTime,
+        record: &Record,
+    ) -> Result<(), std::io::Error> {
+        writeln!(w, "{}", record.level())
+    }
+
+    fn log(logger: &SamplingLogger, level: Level) {
+        logger.log(
+            &Record::builder()
+                .level(level)
+                .args(format_args!("message"))
+                .build(),
+        );
+    }
+
+    // counts the captured lines for each of the levels
+    fn counts(capture: &Capture, drain: &mut LogDrain) -> (usize, usize, usize) {
+        drain.flush().unwrap();
+        let buffer = capture.buffer.lock().unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&buffer).unwrap().lines().collect();
+        let count = |level: &str| lines.iter().filter(|line| **line == level).count();
+        (count("ERROR"), count("WARN"), count("INFO"))
+    }
+
+    // test that a level with its own rate is not sampled with the other levels
+    #[test]
+    fn level_sample_rate() {
+        let capture = Capture::default();
+        let (logger, mut drain) = SamplingLogBuilder::new()
+            .output(Box::new(capture.clone()))
+            .format(level_format)
+            .sample(10)
+            .sample_rate_for_level(LevelFilter::Error, 1)
+            .build_raw()
+            .unwrap();
+
+        for _ in 0..100 {
+            log(&logger, Level::Error);
+            log(&logger, Level::Info);
+        }
+
+        assert_eq!(counts(&capture, &mut drain), (100, 0, 10));
+    }
+
+    // test that levels without their own rate share a single counter
+    #[test]
+    fn shared_sample_rate() {
+        let capture = Capture::default();
+        let (logger, mut drain) = SamplingLogBuilder::new()
+            .output(Box::new(capture.clone()))
+            .format(level_format)
+            .sample(10)
+            .build_raw()
+            .unwrap();
+
+        // alternating levels are sampled 1 in 10 overall, rather than 1 in 10
+        // for each level
+        for _ in 0..50 {
+            log(&logger, Level::Warn);
+            log(&logger, Level::Info);
+        }
+
+        let (error, warn, info) = counts(&capture, &mut drain);
+        assert_eq!(error, 0);
+        assert_eq!(warn + info, 10);
+    }
+}
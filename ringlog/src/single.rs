@@ -155,6 +155,11 @@ impl LogBuilder {
 
     /// Sets the depth of the log queue. Deeper queues are less likely to drop
     /// messages, but come at the cost of additional memory utilization.
+    ///
+    /// A buffer of `single_message_size` bytes is preallocated for every slot
+    /// in the queue, so the logger holds roughly `messages` x
+    /// `single_message_size` bytes of buffers. The default depth is 4096
+    /// messages.
     pub fn log_queue_depth(mut self, messages: usize) -> Self {
         self.log_queue_depth = messages;
         self
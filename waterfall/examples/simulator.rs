@@ -103,7 +103,8 @@ pub fn simulate(shape: Shape) {
                 .label(100000, "100000")
                 .scale(*scale)
                 .palette(*palette)
-                .build(&heatmap);
+                .build(&heatmap)
+                .unwrap();
 
             let filename = format!("{}_{}_{}_smooth.png", shape_name, palette_name, scale_name);
 
@@ -115,7 +116,8 @@ pub fn simulate(shape: Shape) {
                 .scale(*scale)
                 .palette(*palette)
                 .smooth(Some(1.0))
-                .build(&heatmap);
+                .build(&heatmap)
+                .unwrap();
         }
     }
 }
@@ -16,6 +16,13 @@ use rusttype::{point, Font, PositionedGlyph, Scale as TypeScale};
 
 use std::collections::HashMap;
 use std::convert::TryInto;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("gamma must be finite and greater than zero")]
+    InvalidGamma,
+}
 
 #[derive(Copy, Clone)]
 /// Used to configure various strategies for mapping values to colors
@@ -33,6 +40,7 @@ pub struct WaterfallBuilder {
     interval: Duration,
     scale: Scale,
     smooth: Option<f32>,
+    gamma: f32,
 }
 
 impl WaterfallBuilder {
@@ -44,6 +52,7 @@ impl WaterfallBuilder {
             interval: Duration::from_secs(60),
             scale: Scale::Linear,
             smooth: None,
+            gamma: 1.0,
         }
     }
 
@@ -71,6 +80,32 @@ impl WaterfallBuilder {
         self
     }
 
+    /// Set a gamma which is applied to the scaled weights before they are
+    /// mapped to colors. A gamma below 1.0 spreads low weights across more of
+    /// the palette, making rare-but-present buckets easier to see. The default
+    /// of 1.0 maps weights linearly.
+    ///
+    /// The gamma must be finite and greater than zero, otherwise `build` will
+    /// return an error.
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    // get the palette index for a weight, applying the gamma. The scaled weight
+    // is clamped so the index is always within the palette, even for weights
+    // which are negative or undefined, such as with a logarithmic scale.
+    fn palette_index(&self, weight: f64, max_weight: f64, colors: usize) -> usize {
+        let scaled_weight = weight / max_weight;
+        let scaled_weight = if scaled_weight.is_nan() {
+            0.0
+        } else {
+            scaled_weight.clamp(0.0, 1.0)
+        };
+        let scaled_weight = scaled_weight.powf(self.gamma as f64);
+        (scaled_weight * (colors - 1) as f64).round() as usize
+    }
+
     // get the scaled weight for a bucket count / width
     fn weight(&self, count: u64, width: u64) -> f64 {
         match self.scale {
@@ -94,7 +129,11 @@ impl WaterfallBuilder {
     }
 
     /// Generate the waterfall from the provided heatmap
-    pub fn build(self, heatmap: &heatmap::Heatmap) {
+    pub fn build(self, heatmap: &heatmap::Heatmap) -> Result<(), Error> {
+        if !self.gamma.is_finite() || self.gamma <= 0.0 {
+            return Err(Error::InvalidGamma);
+        }
+
         let height = heatmap.active_slices();
         let width = heatmap.buckets();
 
@@ -124,8 +163,7 @@ impl WaterfallBuilder {
             for (y, slice) in heatmap.into_iter().enumerate() {
                 for (x, b) in slice.into_iter().enumerate() {
                     let weight = self.weight(b.count().into(), b.high() - b.low() + 1);
-                    let scaled_weight = weight / max_weight;
-                    let index = (scaled_weight * (colors.len() - 1) as f64).round() as u8;
+                    let index = self.palette_index(weight, max_weight, colors.len()) as u8;
                     buf.put_pixel(
                         x.try_into().unwrap(),
                         y.try_into().unwrap(),
@@ -150,8 +188,7 @@ impl WaterfallBuilder {
             for (y, slice) in heatmap.into_iter().enumerate() {
                 for (x, b) in slice.into_iter().enumerate() {
                     let weight = self.weight(b.count().into(), b.high() - b.low() + 1);
-                    let scaled_weight = weight / max_weight;
-                    let index = (scaled_weight * (colors.len() - 1) as f64).round() as usize;
+                    let index = self.palette_index(weight, max_weight, colors.len());
                     let color = colors[index];
                     buf.put_pixel(
                         x.try_into().unwrap(),
@@ -214,6 +251,8 @@ impl WaterfallBuilder {
             display_time += heatmap.resolution();
         }
         buf.save(&self.output).unwrap();
+
+        Ok(())
     }
 }
 
@@ -257,3 +296,56 @@ fn render_text(string: &str, size: f32, x_pos: usize, y_pos: usize, buf: &mut Rg
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // test that an invalid gamma is rejected when building the waterfall
+    #[test]
+    fn invalid_gamma() {
+        let heatmap = Heatmap::new(
+            0,
+            4,
+            8,
+            Duration::from_secs(1),
+            Duration::from_millis(100),
+            None,
+            None,
+        )
+        .unwrap();
+
+        for gamma in [0.0, -1.0, f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert_eq!(
+                WaterfallBuilder::new("waterfall.png")
+                    .gamma(gamma)
+                    .build(&heatmap),
+                Err(Error::InvalidGamma)
+            );
+        }
+    }
+
+    #[test]
+    fn palette_index() {
+        let linear = WaterfallBuilder::new("waterfall.png");
+        let gamma = WaterfallBuilder::new("waterfall.png").gamma(0.25);
+
+        // a low weight is lost with a linear mapping, but not with a gamma
+        assert_eq!(linear.palette_index(1.0, 1000.0, 256), 0);
+        assert!(gamma.palette_index(1.0, 1000.0, 256) > 0);
+
+        // the extremes map to the ends of the palette for any gamma
+        for builder in [&linear, &gamma] {
+            assert_eq!(builder.palette_index(0.0, 1000.0, 256), 0);
+            assert_eq!(builder.palette_index(1000.0, 1000.0, 256), 255);
+        }
+
+        // an empty heatmap has a max weight of zero
+        assert_eq!(gamma.palette_index(0.0, 0.0, 256), 0);
+
+        // a logarithmic scale can produce negative and infinite weights
+        assert_eq!(gamma.palette_index(-1.0, 10.0, 256), 0);
+        assert_eq!(gamma.palette_index(f64::NEG_INFINITY, 10.0, 256), 0);
+        assert_eq!(gamma.palette_index(f64::INFINITY, 10.0, 256), 255);
+    }
+}